@@ -0,0 +1,636 @@
+use super::*;
+use serde_json::Value;
+
+/// Descriptions of settings, by their path in the config file. Lists don't have an index in the path.
+const DOCS: &[(&str, &str)] = &[
+    ("Spike Percentage", "This controls the global spike values (which are used to determine if a feed is jumping in listeners)"),
+    ("Spike Percentage.Jump Required", "This is the jump multiplier required for a feed to be considered \"jumping\".\n\
+        Ex: If a feed's listeners are 30% higher than its current average listeners, it will be displayed"),
+    ("Spike Percentage.Low Listener Increase", "This controls how much (as a multiplier) the jump required for a feed will increase for every listener it has below the \"Low Listener Cutoff\""),
+    ("Spike Percentage.High Listener Decrease", "This is used along with the \"High Listener Decrease Per Listeners\" value to control how much the jump required will decrease when a feed is jumping to encourage further notifications"),
+    ("Spike Percentage.High Listener Decrease Per Listeners", "This will decrease the jump required multiplier by the \"High Listener Decrease\" value for every x listeners set by this value"),
+    ("Spike Percentage.Minimum Jump", "The fewest listeners a feed has to gain over its average to be considered \"jumping\", no matter how large the jump is as a multiplier"),
+    ("Spike Percentage.Low Listener Cutoff", "Feeds with fewer listeners than this use the \"Low Listener Increase\" value"),
+    ("Spike Percentage.Exit Jump Ratio", "How much of the jump required a feed that is already jumping has to keep to stay jumping. Lower values keep feeds that hover around the jump required\n\
+        from starting and ending incidents over and over. Ex: 0.5 means a feed keeps jumping until its jump falls below half of the jump required"),
+    ("Unskewed Average", "This category contains settings for the average of every feed that isn't biased towards large jumps in listeners. You should very rarely ever have to adjust any of these"),
+    ("Unskewed Average.Reset To Average Percentage", "How close the feed's current listeners have to be to the unskewed average to remove it"),
+    ("Unskewed Average.Adjust to Average Percentage", "How much the unskewed average should slowly inch towards the current feed average to avoid lingering around forever in some cases"),
+    ("Unskewed Average.Spikes Required", "How many times a feed needs to jump consecutively for the unskewed average to be set"),
+    ("Unskewed Average.Jump Required To Set", "How much (as a multiplier) the current listeners of a feed need to be above the saved average to set the unskewed average immediately"),
+    ("Weekday Spike Percentages", "This controls what spike values should be used on a specific day of the week. Any day of the week can be used,\n\
+        and the values of each day are identical to the \"Spike Percentage\" category above. Values that aren't set are taken from it"),
+    ("Time Spike Percentages", "This controls what spike values should be used during certain hours, optionally only on certain days of the week.\n\
+        Ranges that end before they start wrap around midnight, and ranges without hours apply to the whole day. These take precedence over\n\
+        the weekday spike percentages, and when several ranges contain the current time, the first one takes precedence"),
+    ("Time Spike Percentages.Days", "Can be any day of the week, and every day is used when it isn't set"),
+    ("Time Spike Percentages.Spike Percentages", "This category is identical to the \"Spike Percentage\" one above. Values that aren't set are taken from it"),
+    ("Date Overrides", "Settings for holidays and special events. \"Date\" can be a date like \"2018-07-04\", or one like \"07-04\" for every year,\n\
+        and \"End Date\" makes it a range that includes both dates, which have to be written the same way, with or without a year.\n\
+        These take precedence over the weekday and time spike percentages, and when several of them contain the current date, the first one takes precedence"),
+    ("Date Overrides.Spike Percentages", "This category is identical to the \"Spike Percentage\" one above. Values that aren't set are taken from it"),
+    ("Date Overrides.Suppress Notifications", "Keeps feeds from showing notifications on these dates, but statistics are still kept for them"),
+    ("Date Overrides.Skip Learning", "Keeps the listeners on these dates out of the hourly averages, so an unusual day doesn't throw them off"),
+    ("Feed Settings", "Feed-specific settings that apply to a feed name, feed ID, state, or county. \"Name\" can be replaced with \"ID\",\n\
+        \"County\", \"State ID\", \"Name Matches\" or \"County Matches\" (a pattern where * matches anything, ignoring case),\n\
+        \"Name Regex\" or \"County Regex\", \"Listeners\" (with a \"Min\" and/or \"Max\"), or \"All\", \"Any\", and \"Not\" to combine them.\n\
+        Every feed setting that matches a feed is layered on top of the global values, from states to counties to individual feeds"),
+    ("Feed Settings.Priority", "Changes the order this setting is layered in. Defaults to 1 for states, 2 for counties, and 3 for individual feeds"),
+    ("Feed Settings.Spike Percentages", "This category is identical to the \"Spike Percentage\" one above, but it only applies to this feed group.\n\
+        Values that aren't set are taken from the layers below it"),
+    ("Feed Settings.Weekday Spike Percentages", "This category is identical to the \"Weekday Spike Percentages\" one above, but it only applies to this feed group"),
+    ("Feed Settings.Time Spike Percentages", "This category is identical to the \"Time Spike Percentages\" one above, but it only applies to this feed group"),
+    ("Feed Settings.Date Overrides", "This category is identical to the \"Date Overrides\" one above, but it only applies to this feed group"),
+    ("Misc", "Miscellaneous options"),
+    ("Misc.Update Time", "The time in minutes to wait to perform an update"),
+    ("Misc.Minimum Listeners", "Feeds below this value won't show notifications, but statistics are still kept for them"),
+    ("Misc.State Feeds ID", "The state to process extra feed from in an update"),
+    ("Misc.Maximum Feeds To Display", "The most feeds that can be displayed in an update. Every feed that can be displayed is ranked by \"Feed Sorting\" first, so the top ones are shown"),
+    ("Misc.Maximum Feeds Per State", "The most feeds from one state that can be displayed in an update, so one state can't take every spot"),
+    ("Misc.Maximum Feeds Per County", "The most feeds from one county that can be displayed in an update, so one county can't take every spot"),
+    ("Misc.Display Mode", "Controls how feeds are grouped into notifications. Value can be \"Individual\" (one notification per feed),\n\
+        \"Summary\" (one notification for every feed), \"By State\", or \"By County\""),
+    ("Misc.Notify When Incident Ends", "Sends a notification when a feed that was spiking returns to normal"),
+    ("Misc.Update Notifications In Place", "Replaces the notification of a feed (or group of feeds) that was shown in the previous update instead of\n\
+        creating a new one. Only supported on Linux"),
+    ("Feed Sorting", "Changes what order feeds are displayed in"),
+    ("Feed Sorting.Sort By", "Selects what metric feeds should be sorted by. Value can be \"Listeners\", \"Jump\", \"Jump Percentage\", \"Severity\", \"Spike Count\", or \"Alert\"\n\
+        (feeds with an alert come first when descending), or a list of them, where each one breaks ties in the ones before it"),
+    ("Feed Sorting.Sort Order", "Changes what order feeds will be displayed in. Value can either be \"Descending\" or \"Ascending\""),
+    ("Blacklist", "Prevents certain feeds from displaying. Can use any of the ways to match feeds that the feed settings above can,\n\
+        and a feed is hidden if it matches any of them"),
+    ("Blacklist.Until", "The time the entry stops applying, in local time. It applies forever when it isn't set"),
+    ("Whitelist", "Only allows certain feeds to display. Allows the same identification types as the blacklist above, except for \"Until\""),
+    ("Tracking", "Controls which feeds statistics are kept for. Unlike the options above, feeds that are filtered out here won't have\n\
+        accurate statistics if they're allowed again later, so it's best to only use this to limit how many feeds are saved"),
+    ("Tracking.Minimum Listeners", "Feeds below this value are ignored entirely"),
+    ("Tracking.Blacklist", "Identical to the blacklist above"),
+    ("Tracking.Whitelist", "Identical to the whitelist above"),
+    ("Email", "Sends feed updates by email in addition to desktop notifications"),
+    ("Email.SMTP Host", "The server emails are sent through"),
+    ("Email.SMTP Port", "Usually 587 for servers that use STARTTLS, or 25 for ones that don't"),
+    ("Email.Use STARTTLS", "Upgrades the connection to TLS before logging in"),
+    ("Email.Username", "Both of these need to be set to log in"),
+    ("Email.Password", "Saved as plain text, so make sure only you can read this file when it's set"),
+    ("Email.From", "The address emails are sent from"),
+    ("Email.To", "Every address that should receive the emails"),
+    ("Email.Digest Minutes", "Batches every update into one email sent every x minutes. When it is not set, one email is sent per update.\n\
+        Update errors are always sent in their own email"),
+    ("Exec", "Runs a command for every feed update.\n\
+        The feed's information is passed through the BCNOTIF_FEED_ID, BCNOTIF_FEED_NAME, BCNOTIF_FEED_LISTENERS,\n\
+        BCNOTIF_FEED_JUMP, BCNOTIF_FEED_STATE, BCNOTIF_FEED_STATE_ID, BCNOTIF_FEED_COUNTY, BCNOTIF_FEED_ALERT,\n\
+        BCNOTIF_FEED_URL, BCNOTIF_SPIKE_COUNT, BCNOTIF_EVENT, BCNOTIF_SEVERITY, BCNOTIF_TITLE and BCNOTIF_BODY environment variables,\n\
+        as well as JSON through stdin. Update errors and recoveries also run it, with BCNOTIF_EVENT set to \"error\" or \"recovered\",\n\
+        and only pass BCNOTIF_EVENT, BCNOTIF_SEVERITY, BCNOTIF_TITLE, BCNOTIF_BODY, BCNOTIF_FAILURES and BCNOTIF_ERROR"),
+    ("Exec.Command", "The program to run. It isn't run through a shell, so it can't use shell syntax"),
+    ("Exec.Arguments", "Passed to the command as they are, without any placeholders being filled in"),
+    ("Exec.Timeout Seconds", "The command is killed if it takes longer than this"),
+    ("MQTT", "Publishes feed updates to an MQTT broker.\n\
+        Spikes, alerts, and update errors are published as JSON to \"<Topic Prefix>/events\", and \"<Topic Prefix>/status\" is set to\n\
+        \"online\" while the program is running and \"offline\" (through a last will message) when it isn't"),
+    ("MQTT.Host", "The broker to connect to"),
+    ("MQTT.Port", "Usually 1883"),
+    ("MQTT.Client ID", "Identifies this program to the broker, so it should be different for each copy of it that connects"),
+    ("MQTT.Username", "Both of these are optional. The password is only sent along with a username"),
+    ("MQTT.Password", "Saved as plain text, so make sure only you can read this file when it's set"),
+    ("MQTT.Topic Prefix", "Every topic is published under this one"),
+    ("MQTT.QoS", "The quality of service messages are published with. Can be 0, 1, or 2"),
+    ("MQTT.Retain", "Makes the broker keep the last message of every topic for clients that connect later"),
+    ("MQTT.Publish All Feed Stats", "Publishes every feed's listeners to \"<Topic Prefix>/feed/<id>/listeners\" and its statistics as JSON\n\
+        to \"<Topic Prefix>/feed/<id>/stats\" on every update"),
+    ("Templates", "Changes the text of notifications for each backend (\"Desktop\", \"Email\", \"Exec\", or \"MQTT\") and event (\"Spike\", \"Alert\", \"Incident Ended\", \"Error\", or \"Recovered\")\n\
+        Any event that isn't set uses the built-in text. Available placeholders are {event}, {severity}, {id}, {name}, {listeners}, {state}, {state_id},\n\
+        {county}, {alert}, {alert_line}, {url}, {jump}, {jump_percent}, {baseline}, {average}, {hourly_average}, {hourly_norm},\n\
+        {spike_count}, and {duration}. Desktop notifications can also use {index} and {total}, error notifications only have {error} and {failures},\n\
+        and recovered notifications only have {failures}"),
+    ("Templates.Desktop.Summary Title", "Used by the \"Summary\", \"By State\", and \"By County\" display modes. The title can use {group} and {total}"),
+    ("Templates.Desktop.Summary Line", "Each feed's line in the body of those notifications"),
+    ("Severity", "Classifies every feed update as \"Minor\", \"Major\", or \"Critical\". An update is major or critical when either\n\
+        its jump (as a multiplier of its average listeners) or its total listeners reach the threshold for that level"),
+    ("Severity.Major Jump Ratio", "The jump (as a multiplier of a feed's average listeners) that makes an update major"),
+    ("Severity.Major Listeners", "The listeners that make an update major"),
+    ("Severity.Critical Jump Ratio", "The jump (as a multiplier of a feed's average listeners) that makes an update critical"),
+    ("Severity.Critical Listeners", "The listeners that make an update critical"),
+    ("Severity.Alert Severity", "The lowest severity a feed with an alert can have"),
+    ("Severity.Minor", "Changes how desktop notifications look for each severity.\n\
+        Urgency can be \"Low\", \"Normal\", or \"Critical\", and defaults to the same level as the severity (with minor being low)\n\
+        The icon defaults to \"emblem-sound\", or \"dialog-warning\" for critical updates. Timeout Seconds is left up to the\n\
+        notification server when it isn't set, and a value of 0 means the notification never expires\n\
+        Sounds are only supported on Linux and use names from the freedesktop sound theme"),
+    ("Severity.Major", "Identical to \"Minor\" above"),
+    ("Severity.Critical", "Identical to \"Minor\" above"),
+    ("Notification Actions", "Adds \"Open feed\", \"Mute 1h\", and \"Mute feed\" buttons to individual feed notifications. Only supported on Linux\n\
+        Muted feeds are saved to \"mutes.csv\" in the data directory, and can be managed with the mute, unmute, and mutes commands"),
+    ("Notification Actions.Enabled", "Shows the buttons. Muted feeds stay muted when this is turned off"),
+    ("Notification Actions.Open Command", "The command used to open a feed. The feed's link is passed as the last argument"),
+    ("Notification Actions.Open Arguments", "Passed to the open command before the feed's link"),
+    ("Notification Actions.Temporary Mute Hours", "How long the \"Mute\" button mutes a feed for"),
+    ("Schedule", "Changes how notifications are sent during certain times. The first window that contains the current time is used,\n\
+        and notifications are sent normally outside of every window. Feeds are still monitored during every window.\n\
+        Windows that end before they start wrap around midnight, and windows without days or hours apply all of the time"),
+    ("Schedule.Backends", "Can be \"Desktop\", \"Email\", \"Exec\", or \"MQTT\", and every backend is used when it isn't set"),
+    ("Schedule.Minimum Severity", "Updates below this severity are suppressed during the window. Can be \"Minor\", \"Major\", or \"Critical\""),
+    ("Schedule.Summarize Suppressed", "Shows a desktop notification listing every feed that was suppressed once the window ends.\n\
+        Feeds that weren't shown because the window doesn't use the desktop are listed too, and the notification waits until a window that does"),
+    ("Error Notifications", "Controls error notifications while updates keep failing, such as when the network is down.\n\
+        A notification is always shown for the first failure in a row, and it's sent through every backend that the schedule allows"),
+    ("Error Notifications.Remind After Failures", "Shows another error notification after this many more failures in a row"),
+    ("Error Notifications.Notify When Recovered", "Shows a notification when an update works again after failing"),
+    ("Notification Retries", "Notifications that fail to be delivered through any backend are saved to \"outbox.json\" in the data\n\
+        directory and retried on later updates. The delay doubles after every failed attempt"),
+    ("Notification Retries.Initial Delay Minutes", "How long to wait before the first retry"),
+    ("Notification Retries.Maximum Delay Minutes", "The delay stops doubling once it reaches this"),
+    ("Notification Retries.Maximum Attempts", "Notifications are dropped after failing this many times"),
+    ("Notification Retries.Maximum Age Minutes", "Desktop notifications and commands are dropped once they're this old, since they're about what a feed\n\
+        is doing right now. Emails and MQTT messages are retried until they run out of attempts"),
+];
+
+/// Gets the limits of the settings in a group, by the name of the group.
+fn limits(group: &str) -> &'static [Limit] {
+    match group {
+        "Spike Percentage" => Spike::LIMITS,
+        "Spike Percentages" | "Sunday" | "Monday" | "Tuesday" | "Wednesday" | "Thursday"
+        | "Friday" | "Saturday" => SpikeOverride::LIMITS,
+        "Unskewed Average" => UnskewedAverage::LIMITS,
+        "Misc" => Misc::LIMITS,
+        "Email" => Email::LIMITS,
+        "Exec" => Exec::LIMITS,
+        "MQTT" => Mqtt::LIMITS,
+        "Severity" => Severities::LIMITS,
+        "Minor" | "Major" | "Critical" => SeverityStyle::LIMITS,
+        "Notification Actions" => Actions::LIMITS,
+        "Schedule" | "Time Spike Percentages" => HourRange::LIMITS,
+        "Error Notifications" => ErrorNotifications::LIMITS,
+        "Notification Retries" => Retries::LIMITS,
+        _ => &[],
+    }
+}
+
+/// Settings that aren't set by default, which are written to the generated config file as commented out examples.
+fn example() -> Config {
+    let spike = |jump, high_listener_dec| SpikeOverride {
+        jump: Some(jump),
+        high_listener_dec: Some(high_listener_dec),
+        ..SpikeOverride::default()
+    };
+
+    let templates = Templates {
+        spike: Template {
+            title: Some("{state} - Broadcastify Update ({index} of {total})".into()),
+            body: Some("Name: {name}\nListeners: {listeners} (^{jump}, {jump_percent}%){alert_line}\nLink: {url}".into()),
+        },
+        incident_ended: Template {
+            title: Some("{state} - {name} has calmed down".into()),
+            body: Some("Spiked {spike_count} times over {duration}".into()),
+        },
+        summary_title: Some("{group} - Broadcastify Update ({total} feeds)".into()),
+        summary_line: Some("{state} - {name}: {listeners} (^{jump}){alert_line}".into()),
+        ..Templates::default()
+    };
+
+    let critical = SeverityStyle {
+        urgency: Some(Urgency::Critical),
+        timeout: Some(0.0),
+        icon: Some("dialog-warning".into()),
+        sound: Some("alarm-clock-elapsed".into()),
+    };
+
+    Config {
+        weekday_spikes: vec![
+            WeekdaySpike::Saturday(spike(0.3, 0.03)),
+            WeekdaySpike::Sunday(spike(0.3, 0.03)),
+        ],
+        time_spikes: vec![TimeSpike {
+            hours: HourRange {
+                days: vec![Day::Friday, Day::Saturday],
+                start_hour: 23,
+                end_hour: 3,
+            },
+            spike: spike(0.5, 0.02),
+        }],
+        date_overrides: vec![
+            DateOverride {
+                description: Some("Independence Day".into()),
+                date: ConfigDate::parse("07-04".into()).unwrap(),
+                end_date: None,
+                spike: spike(0.5, 0.02),
+                suppress_notifications: false,
+                skip_learning: true,
+            },
+            DateOverride {
+                description: Some("Maintenance".into()),
+                date: ConfigDate::parse("2018-06-01".into()).unwrap(),
+                end_date: ConfigDate::parse("2018-06-03".into()),
+                spike: SpikeOverride::default(),
+                suppress_notifications: true,
+                skip_learning: true,
+            },
+        ],
+        feed_settings: vec![
+            FeedSetting {
+                ident: FeedIdent::Name("Test Feed".into()),
+                priority: None,
+                spike: spike(0.3, 0.02),
+                weekday_spikes: vec![WeekdaySpike::Friday(spike(0.4, 0.02))],
+                time_spikes: vec![TimeSpike {
+                    hours: HourRange {
+                        start_hour: 6,
+                        end_hour: 8,
+                        ..HourRange::default()
+                    },
+                    spike: spike(0.4, 0.02),
+                }],
+                date_overrides: Vec::new(),
+            },
+            FeedSetting {
+                ident: FeedIdent::All(vec![
+                    FeedIdent::County("Sacramento".into()),
+                    FeedIdent::NameGlob(Pattern::glob("*fire*".into()).unwrap()),
+                ]),
+                priority: Some(4),
+                spike: spike(0.25, 0.02),
+                weekday_spikes: Vec::new(),
+                time_spikes: Vec::new(),
+                date_overrides: vec![DateOverride {
+                    description: None,
+                    date: ConfigDate::parse("12-31".into()).unwrap(),
+                    end_date: ConfigDate::parse("01-01".into()),
+                    spike: spike(0.4, 0.02),
+                    suppress_notifications: false,
+                    skip_learning: false,
+                }],
+            },
+        ],
+        misc: Misc {
+            state_feeds_id: Some(6),
+            max_feeds_per_state: Some(5),
+            max_feeds_per_county: Some(2),
+            ..Misc::default()
+        },
+        blacklist: vec![
+            BlacklistEntry {
+                ident: FeedIdent::ID(0),
+                until: None,
+            },
+            BlacklistEntry {
+                ident: FeedIdent::County("Nowhereville".into()),
+                until: None,
+            },
+            BlacklistEntry {
+                ident: FeedIdent::NameRegex(Pattern::regex("(?i)interop|test".into()).unwrap()),
+                until: Timestamp::parse("2018-06-01 18:00".into()),
+            },
+        ],
+        whitelist: vec![FeedIdent::State(6), FeedIdent::Name("Test Feed".into())],
+        tracking: Tracking {
+            blacklist: vec![BlacklistEntry {
+                ident: FeedIdent::Name("Test Feed".into()),
+                until: None,
+            }],
+            ..Tracking::default()
+        },
+        email: Some(Email {
+            host: "localhost".into(),
+            port: default_smtp_port(),
+            starttls: true,
+            username: Some("bcnotif".into()),
+            password: Some("<password>".into()),
+            from: "bcnotif@example.com".into(),
+            to: vec!["oncall@example.com".into()],
+            digest_minutes: Some(30.0),
+        }),
+        exec: vec![Exec {
+            command: "/usr/local/bin/start-recorder".into(),
+            args: vec!["--quiet".into()],
+            timeout: default_exec_timeout(),
+        }],
+        mqtt: Some(Mqtt {
+            host: "localhost".into(),
+            port: default_mqtt_port(),
+            client_id: default_mqtt_name(),
+            username: Some("bcnotif".into()),
+            password: Some("<password>".into()),
+            topic_prefix: default_mqtt_name(),
+            qos: 0,
+            retain: false,
+            publish_stats: false,
+        }),
+        templates: BackendTemplates {
+            desktop: templates,
+            ..BackendTemplates::default()
+        },
+        severity: Severities {
+            critical,
+            ..Severities::default()
+        },
+        schedule: vec![
+            ScheduleWindow {
+                hours: HourRange {
+                    start_hour: 22,
+                    end_hour: 7,
+                    ..HourRange::default()
+                },
+                min_severity: Severity::Critical,
+                summarize: true,
+                ..ScheduleWindow::default()
+            },
+            ScheduleWindow {
+                hours: HourRange {
+                    days: vec![
+                        Day::Monday,
+                        Day::Tuesday,
+                        Day::Wednesday,
+                        Day::Thursday,
+                        Day::Friday,
+                    ],
+                    start_hour: 9,
+                    end_hour: 17,
+                },
+                backends: vec![Backend::Email],
+                ..ScheduleWindow::default()
+            },
+        ],
+        ..Config::default()
+    }
+}
+
+/// A line of the generated config file.
+struct Line {
+    indent: usize,
+    description: bool,
+    commented: bool,
+    text: String,
+}
+
+impl Config {
+    /// Generates a config file with every setting set to its default value, along with its description and limits.
+    /// Settings that aren't set by default are included as commented out examples.
+    pub fn annotated_default() -> Result<String, ConfigError> {
+        let defaults = Config::default().to_json()?;
+        let example = example().to_json()?;
+
+        let mut output = String::from(
+            "# Every setting is set to its default value, so any of them can be removed.\n\
+             # Settings that are commented out aren't set by default, and show an example of how they're used.\n",
+        );
+
+        if let Value::Object(ref fields) = defaults {
+            for (key, value) in fields {
+                let mut lines = Vec::new();
+                write_entry(&mut lines, key, key, value, example.get(key), 0, false);
+
+                output.push('\n');
+
+                for line in lines {
+                    output.push_str(&" ".repeat(line.indent));
+
+                    if line.description || line.commented {
+                        output.push_str("# ");
+                    }
+
+                    output.push_str(&line.text);
+                    output.push('\n');
+                }
+            }
+        }
+
+        Ok(output)
+    }
+}
+
+/// Writes a setting with its description. Settings that are empty are written with their example instead, if there is one.
+fn write_entry(
+    lines: &mut Vec<Line>,
+    path: &str,
+    key: &str,
+    value: &Value,
+    example: Option<&Value>,
+    indent: usize,
+    commented: bool,
+) {
+    if let Some(&(_, doc)) = DOCS.iter().find(|&&(doc_path, _)| doc_path == path) {
+        for text in doc.lines() {
+            lines.push(Line {
+                indent,
+                description: true,
+                commented: false,
+                text: text.trim().into(),
+            });
+        }
+    }
+
+    let limit = find_limit(path).and_then(|limit| match (limit.min, limit.max) {
+        (Some(min), Some(max)) => Some(format!("Can be from {} to {}", min, max)),
+        (Some(min), None) => Some(format!("Can't be lower than {}", min)),
+        (None, Some(max)) => Some(format!("Can't be higher than {}", max)),
+        (None, None) => None,
+    });
+
+    if let Some(text) = limit {
+        lines.push(Line {
+            indent,
+            description: true,
+            commented: false,
+            text,
+        });
+    }
+
+    match example {
+        Some(example) if is_empty(value) && !is_empty(example) && !commented => {
+            write_value(lines, path, key, example, None, indent, true)
+        }
+        _ => write_value(lines, path, key, value, example, indent, commented),
+    }
+}
+
+fn write_value(
+    lines: &mut Vec<Line>,
+    path: &str,
+    key: &str,
+    value: &Value,
+    example: Option<&Value>,
+    indent: usize,
+    commented: bool,
+) {
+    match *value {
+        Value::Object(ref fields) if !is_empty(value) => {
+            lines.push(Line {
+                indent,
+                description: false,
+                commented,
+                text: format!("{}:", key),
+            });
+
+            for (child, value) in fields {
+                let path = format!("{}.{}", path, child);
+                let example = example.and_then(|example| example.get(child));
+
+                write_entry(lines, &path, child, value, example, indent + 2, commented);
+            }
+        }
+        // Lists of groups are written as a list of blocks, since they would be hard to read on one line
+        Value::Array(ref items) if items.iter().any(Value::is_object) => {
+            lines.push(Line {
+                indent,
+                description: false,
+                commented,
+                text: format!("{}:", key),
+            });
+
+            for item in items {
+                let mut item_lines = Vec::new();
+
+                if let Value::Object(ref fields) = *item {
+                    for (child, value) in fields {
+                        let path = format!("{}.{}", path, child);
+                        write_entry(
+                            &mut item_lines,
+                            &path,
+                            child,
+                            value,
+                            None,
+                            indent + 4,
+                            commented,
+                        );
+                    }
+                }
+
+                // The first setting of the group starts the list item
+                let mut first = true;
+
+                for mut line in item_lines {
+                    if first && !line.description {
+                        line.indent -= 2;
+                        line.text = format!("- {}", line.text);
+                        first = false;
+                    }
+
+                    lines.push(line);
+                }
+            }
+        }
+        _ => lines.push(Line {
+            indent,
+            description: false,
+            commented,
+            text: format!("{}: {}", key, format_value(value)),
+        }),
+    }
+}
+
+/// Finds the limits of the setting at the specified path.
+fn find_limit(path: &str) -> Option<&'static Limit> {
+    let mut keys = path.rsplit('.');
+    let key = keys.next()?;
+    let group = keys.next()?;
+
+    limits(group).iter().find(|limit| limit.key == key)
+}
+
+/// Returns true if the value isn't set, or is a group of settings where none of them are set.
+fn is_empty(value: &Value) -> bool {
+    match *value {
+        Value::Null => true,
+        Value::Array(ref items) => items.is_empty(),
+        Value::Object(ref fields) => fields.iter().all(|(_, value)| is_empty(value)),
+        _ => false,
+    }
+}
+
+/// Formats a value on one line. JSON is used since it's also valid YAML.
+fn format_value(value: &Value) -> String {
+    match *value {
+        Value::Null => "~".into(),
+        Value::Object(_) if is_empty(value) => "{}".into(),
+        _ => value.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Collects the path of every setting in the same form that `DOCS` uses.
+    fn collect_paths(value: &Value, path: Option<&str>, paths: &mut Vec<String>) {
+        match *value {
+            Value::Object(ref fields) => {
+                for (key, value) in fields {
+                    let path = match path {
+                        Some(path) => format!("{}.{}", path, key),
+                        None => key.clone(),
+                    };
+
+                    collect_paths(value, Some(&path), paths);
+                    paths.push(path);
+                }
+            }
+            Value::Array(ref items) => {
+                for item in items {
+                    collect_paths(item, path, paths);
+                }
+            }
+            _ => (),
+        }
+    }
+
+    /// Groups whose description covers every setting in them, since they're the same for each entry.
+    const DESCRIBED_GROUPS: &[&str] = &[
+        "Templates",
+        "Severity.Minor",
+        "Severity.Major",
+        "Severity.Critical",
+    ];
+
+    #[test]
+    fn every_setting_has_a_description() {
+        let mut paths = Vec::new();
+        collect_paths(&Config::default().to_json().unwrap(), None, &mut paths);
+
+        let undescribed = paths
+            .iter()
+            .filter(|path| !DOCS.iter().any(|&(doc_path, _)| doc_path == path.as_str()))
+            .filter(|path| {
+                !DESCRIBED_GROUPS
+                    .iter()
+                    .any(|group| path.starts_with(&format!("{}.", group)))
+            })
+            .collect::<Vec<_>>();
+
+        assert!(
+            undescribed.is_empty(),
+            "settings without a description: {:?}",
+            undescribed
+        );
+    }
+
+    #[test]
+    fn annotated_default_is_the_default() {
+        let contents = Config::annotated_default().unwrap();
+        let (config, issues) = Config::parse(&contents, Format::Yaml, "config.yaml").unwrap();
+
+        assert!(issues.is_empty(), "{:?}", issues);
+        assert_eq!(
+            config.to_json().unwrap(),
+            Config::default().to_json().unwrap()
+        );
+    }
+
+    #[test]
+    fn every_description_belongs_to_a_setting() {
+        let mut paths = Vec::new();
+        collect_paths(&Config::default().to_json().unwrap(), None, &mut paths);
+        collect_paths(&example().to_json().unwrap(), None, &mut paths);
+
+        let unknown = DOCS
+            .iter()
+            .map(|&(path, _)| path)
+            .filter(|path| !paths.iter().any(|known| known == path))
+            .collect::<Vec<_>>();
+
+        assert!(
+            unknown.is_empty(),
+            "descriptions without a setting: {:?}",
+            unknown
+        );
+    }
+}
@@ -0,0 +1,493 @@
+use super::{remove_nulls, Config, ConfigError, Format};
+use serde::de;
+use serde_json::{self, Map, Value};
+use serde_yaml;
+use std::collections::HashMap;
+use toml;
+use yaml_rust::parser::{Event, MarkedEventReceiver, Parser};
+use yaml_rust::scanner::Marker;
+use yaml_rust::YamlLoader;
+
+/// A step in the path to a setting.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Key {
+    Name(String),
+    Index(usize),
+}
+
+/// Reads the config contents into JSON values, which every format can be converted to.
+pub fn read(contents: &str, format: Format, display_path: &str) -> Result<Value, ConfigError> {
+    let mut value = match format {
+        Format::Yaml => {
+            // A file with only comments doesn't have a document to read the config from
+            match YamlLoader::load_from_str(contents) {
+                Ok(ref docs) if docs.is_empty() => return Ok(Value::Object(Map::new())),
+                _ => (),
+            }
+
+            serde_yaml::from_str(contents).map_err(|e| ConfigError::Yaml(e, display_path.into()))?
+        }
+        Format::Toml => {
+            let value = contents
+                .parse::<toml::Value>()
+                .map_err(|e| ConfigError::Toml(e, display_path.into()))?;
+
+            from_toml(value)
+        }
+        Format::Json => {
+            serde_json::from_str(contents).map_err(|e| ConfigError::Json(e, display_path.into()))?
+        }
+    };
+
+    // Keys without a value are treated as if they weren't set
+    if value.is_null() {
+        value = Value::Object(Map::new());
+    }
+
+    remove_nulls(&mut value);
+
+    Ok(value)
+}
+
+/// Converts a TOML value to JSON. The toml crate can only read enums with values from inline tables,
+/// so going through JSON allows them in regular tables too.
+fn from_toml(value: toml::Value) -> Value {
+    match value {
+        toml::Value::String(s) => Value::String(s),
+        toml::Value::Integer(num) => num.into(),
+        toml::Value::Float(num) => num.into(),
+        toml::Value::Boolean(b) => Value::Bool(b),
+        // Dates and times in the config are read from text
+        toml::Value::Datetime(time) => Value::String(time.to_string()),
+        toml::Value::Array(items) => Value::Array(items.into_iter().map(from_toml).collect()),
+        toml::Value::Table(table) => Value::Object(
+            table
+                .into_iter()
+                .map(|(key, value)| (key, from_toml(value)))
+                .collect(),
+        ),
+    }
+}
+
+/// Reads the config from the values in the file. Each section is read on its own, and one that can't be
+/// read has the settings or list items that are causing it left out and reported, so they use their default.
+///
+/// Settings that are left out are removed from the values, and list items are replaced with null so the
+/// position of every other item stays the same.
+pub fn deserialize(
+    value: &mut Value,
+    problems: &mut Vec<(Vec<Key>, String)>,
+) -> Result<Config, serde_json::Error> {
+    let defaults = serde_json::to_value(Config::default())?;
+
+    let sections = match *value {
+        Value::Object(ref mut sections) => sections,
+        _ => {
+            return Err(de::Error::custom(
+                "expected a group of settings at the top of the file",
+            ))
+        }
+    };
+
+    for (name, section) in sections.iter_mut() {
+        let path = vec![Key::Name(name.clone())];
+        prune_section(name, section, defaults.get(name), &path, problems);
+    }
+
+    let dropped = sections
+        .iter()
+        .filter(|&(_, section)| section.is_null())
+        .map(|(name, _)| name.clone())
+        .collect::<Vec<_>>();
+
+    for name in dropped {
+        sections.remove(&name);
+    }
+
+    // Items that were left out of lists are only kept as null to keep the positions of the others
+    let mut sections = sections.clone();
+
+    for (_, section) in sections.iter_mut() {
+        if let Value::Array(ref mut items) = *section {
+            items.retain(|item| !item.is_null());
+        }
+    }
+
+    let mut value = Value::Object(sections);
+    rename_aliases(&mut value);
+
+    serde_json::from_value(value)
+}
+
+/// Keys that older versions documented under a different name, paired with the name they're saved under.
+const ALIASES: &[(&str, &str)] = &[(
+    "Adjust To Average Percentage",
+    "Adjust to Average Percentage",
+)];
+
+/// Gets the name a key is saved under, which is only different for the keys in `ALIASES`.
+pub fn canonical(key: &str) -> &str {
+    ALIASES
+        .iter()
+        .find(|&&(alias, _)| alias == key)
+        .map_or(key, |&(_, name)| name)
+}
+
+/// Renames the keys in `ALIASES` to the names they're saved under, in every group of settings.
+fn rename_aliases(value: &mut Value) {
+    match *value {
+        Value::Object(ref mut fields) => {
+            for &(alias, name) in ALIASES {
+                if let Some(field) = fields.remove(alias) {
+                    fields.insert(name.into(), field);
+                }
+            }
+
+            for (_, field) in fields.iter_mut() {
+                rename_aliases(field);
+            }
+        }
+        Value::Array(ref mut items) => {
+            for item in items.iter_mut() {
+                rename_aliases(item);
+            }
+        }
+        _ => (),
+    }
+}
+
+/// Leaves out the parts of a section that can't be read. The whole section is set to null when that isn't enough.
+fn prune_section(
+    name: &str,
+    section: &mut Value,
+    default: Option<&Value>,
+    path: &[Key],
+    problems: &mut Vec<(Vec<Key>, String)>,
+) {
+    if read_section(name, section.clone()).is_ok() {
+        return;
+    }
+
+    match (&mut *section, default) {
+        (&mut Value::Array(ref mut items), _) => {
+            for (i, item) in items.iter_mut().enumerate() {
+                if let Err(err) = read_section(name, Value::Array(vec![item.clone()])) {
+                    let mut path = path.to_vec();
+                    path.push(Key::Index(i));
+
+                    let msg = format!("an item in \"{}\" will be ignored: {}", name, err);
+                    problems.push((path, msg));
+
+                    *item = Value::Null;
+                }
+            }
+        }
+        // Each setting is read along with the default values of the others, so settings that are needed
+        // for the group to be read don't have to be set
+        (&mut Value::Object(ref mut fields), Some(Value::Object(defaults))) => {
+            let mut dropped = Vec::new();
+
+            for (key, field) in fields.iter() {
+                let mut group = defaults.clone();
+                group.insert(key.clone(), field.clone());
+
+                if let Err(err) = read_section(name, Value::Object(group)) {
+                    let mut path = path.to_vec();
+                    path.push(Key::Name(key.clone()));
+
+                    let msg = format!("\"{}\" in \"{}\" will be ignored: {}", key, name, err);
+                    problems.push((path, msg));
+
+                    dropped.push(key.clone());
+                }
+            }
+
+            for key in dropped {
+                fields.remove(&key);
+            }
+        }
+        _ => (),
+    }
+
+    let remaining = match section.clone() {
+        Value::Array(items) => Value::Array(items.into_iter().filter(|i| !i.is_null()).collect()),
+        other => other,
+    };
+
+    if let Err(err) = read_section(name, remaining) {
+        let msg = format!("\"{}\" in \"config\" will be ignored: {}", name, err);
+        problems.push((path.to_vec(), msg));
+
+        *section = Value::Null;
+    }
+}
+
+/// Reads a config that only has the specified section set.
+fn read_section(name: &str, section: Value) -> Result<Config, serde_json::Error> {
+    let mut sections = Map::new();
+    sections.insert(name.into(), section);
+
+    let mut value = Value::Object(sections);
+    rename_aliases(&mut value);
+
+    serde_json::from_value(value)
+}
+
+/// Finds the line and column of the setting at the specified path, both starting at 1. Settings in inline
+/// TOML tables are found at the key the table is under, and JSON files that can't be read as YAML don't have positions.
+pub fn position(contents: &str, format: Format, path: &[Key]) -> Option<(usize, usize)> {
+    match format {
+        // JSON is a subset of YAML, so its positions are found the same way
+        Format::Yaml | Format::Json => yaml_position(contents, path),
+        Format::Toml => toml_position(contents, path),
+    }
+}
+
+enum Frame {
+    /// The key whose value is being read, if any.
+    Map(Option<String>),
+    /// The index of the next item.
+    Seq(usize),
+}
+
+/// Follows the path to a setting through the events of the YAML parser.
+struct Locator<'a> {
+    target: &'a [Key],
+    path: Vec<Key>,
+    frames: Vec<Frame>,
+    found: Option<Marker>,
+    /// Where the group that was found starts, when it's in a list. It's found at its first key instead.
+    group_start: Option<Marker>,
+}
+
+impl<'a> Locator<'a> {
+    fn begin(&mut self, mark: Marker, scalar: Option<String>, frame: Option<Frame>) {
+        let is_key = matches!(self.frames.last(), Some(&Frame::Map(None)));
+
+        if is_key {
+            let name = scalar.unwrap_or_default();
+
+            if self.group_start.take().is_some() || self.is_target(&self.path, &name) {
+                self.found = Some(mark);
+            }
+
+            if let Some(&mut Frame::Map(ref mut key)) = self.frames.last_mut() {
+                *key = Some(name);
+            }
+
+            // Groups can't be used as keys in the config, but they still have to be followed to their end
+            if let Some(frame) = frame {
+                self.frames.push(frame);
+                self.path.push(Key::Name(String::new()));
+            }
+
+            return;
+        }
+
+        let segment = match self.frames.last_mut() {
+            Some(&mut Frame::Map(ref mut key)) => key.take().map(Key::Name),
+            Some(&mut Frame::Seq(ref mut index)) => {
+                *index += 1;
+                Some(Key::Index(*index - 1))
+            }
+            None => None,
+        };
+
+        let in_list = matches!(segment, Some(Key::Index(_)));
+
+        match segment {
+            Some(segment) => self.path.push(segment),
+            // The document itself isn't part of the path
+            None => {
+                self.frames.extend(frame);
+                return;
+            }
+        }
+
+        if self.path.as_slice() == self.target {
+            match frame {
+                Some(Frame::Map(_)) if in_list => self.group_start = Some(mark),
+                _ => self.found = Some(mark),
+            }
+        }
+
+        match frame {
+            Some(frame) => self.frames.push(frame),
+            None => {
+                self.path.pop();
+            }
+        }
+    }
+
+    fn end(&mut self) {
+        // A group without any keys is found where it starts
+        if let Some(start) = self.group_start.take() {
+            self.found = Some(start);
+        }
+
+        self.frames.pop();
+
+        if !self.frames.is_empty() {
+            self.path.pop();
+        }
+    }
+
+    /// Returns true if the key under the specified path is the one being looked for.
+    fn is_target(&self, path: &[Key], name: &str) -> bool {
+        self.target.len() == path.len() + 1
+            && self.target.starts_with(path)
+            && self.target.last() == Some(&Key::Name(name.into()))
+    }
+}
+
+impl<'a> MarkedEventReceiver for Locator<'a> {
+    fn on_event(&mut self, ev: Event, mark: Marker) {
+        if self.found.is_some() {
+            return;
+        }
+
+        match ev {
+            Event::MappingStart(_) => self.begin(mark, None, Some(Frame::Map(None))),
+            Event::SequenceStart(_) => self.begin(mark, None, Some(Frame::Seq(0))),
+            Event::Scalar(value, _, _, _) => self.begin(mark, Some(value), None),
+            Event::Alias(_) => self.begin(mark, None, None),
+            Event::MappingEnd | Event::SequenceEnd => self.end(),
+            _ => (),
+        }
+    }
+}
+
+fn yaml_position(contents: &str, path: &[Key]) -> Option<(usize, usize)> {
+    let mut locator = Locator {
+        target: path,
+        path: Vec::new(),
+        frames: Vec::new(),
+        found: None,
+        group_start: None,
+    };
+
+    Parser::new(contents.chars())
+        .load(&mut locator, false)
+        .ok()?;
+
+    locator.found.map(|mark| (mark.line(), mark.col() + 1))
+}
+
+/// Finds a setting by going through the table headers and keys of a TOML file line by line.
+fn toml_position(contents: &str, target: &[Key]) -> Option<(usize, usize)> {
+    // The number of tables in each list of tables that has been found so far
+    let mut lists: HashMap<Vec<Key>, usize> = HashMap::new();
+    let mut table = Vec::new();
+    let mut in_string = false;
+
+    for (i, line) in contents.lines().enumerate() {
+        let text = line.trim_start();
+        let position = (i + 1, line.len() - text.len() + 1);
+
+        // Multi-line strings can have anything in them
+        let quotes = text.matches("\"\"\"").count() + text.matches("'''").count();
+
+        if in_string || text.starts_with('#') {
+            in_string ^= quotes % 2 == 1;
+            continue;
+        }
+
+        if text.starts_with('[') {
+            let is_list = text.starts_with("[[");
+
+            let keys = match toml_keys(text.trim_start_matches('[')) {
+                Some((keys, rest)) if rest.starts_with(']') => keys,
+                _ => continue,
+            };
+
+            let last = keys.len() - 1;
+            table.clear();
+
+            for (j, key) in keys.into_iter().enumerate() {
+                table.push(Key::Name(key));
+
+                if is_list && j == last {
+                    // Every header of a list of tables starts a new table in it
+                    let count = lists.entry(table.clone()).or_insert(0);
+                    *count += 1;
+                    table.push(Key::Index(*count - 1));
+                } else if let Some(&count) = lists.get(&table) {
+                    table.push(Key::Index(count - 1));
+                }
+            }
+
+            if table.starts_with(target) {
+                return Some(position);
+            }
+
+            continue;
+        }
+
+        if let Some((keys, rest)) = toml_keys(text) {
+            if rest.starts_with('=') {
+                let mut path = table.clone();
+                path.extend(keys.into_iter().map(Key::Name));
+
+                // Values in inline tables and arrays are found at the key they're under
+                if target.starts_with(&path) || path.starts_with(target) {
+                    return Some(position);
+                }
+            }
+        }
+
+        in_string ^= quotes % 2 == 1;
+    }
+
+    None
+}
+
+/// Reads the dotted TOML key at the start of the text, and returns it with the text that comes after it.
+fn toml_keys(text: &str) -> Option<(Vec<String>, &str)> {
+    let mut keys = Vec::new();
+    let mut rest = text;
+
+    loop {
+        rest = rest.trim_start();
+
+        let (key, len) = match rest.chars().next()? {
+            quote @ '"' | quote @ '\'' => {
+                let mut key = String::new();
+                let mut chars = rest.char_indices().skip(1);
+                let mut end = None;
+
+                while let Some((i, c)) = chars.next() {
+                    match c {
+                        '\\' if quote == '"' => key.extend(chars.next().map(|(_, c)| c)),
+                        c if c == quote => {
+                            end = Some(i + 1);
+                            break;
+                        }
+                        c => key.push(c),
+                    }
+                }
+
+                (key, end?)
+            }
+            _ => {
+                let len = rest
+                    .find(|c: char| !c.is_alphanumeric() && c != '_' && c != '-')
+                    .unwrap_or(rest.len());
+
+                if len == 0 {
+                    return None;
+                }
+
+                (rest[..len].to_string(), len)
+            }
+        };
+
+        keys.push(key);
+        rest = rest[len..].trim_start();
+
+        if rest.starts_with('.') {
+            rest = &rest[1..];
+        } else {
+            return Some((keys, rest));
+        }
+    }
+}
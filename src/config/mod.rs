@@ -1,42 +1,403 @@
-#[macro_use]
-mod generation;
+mod annotated;
+mod load;
+mod pattern;
+mod validate;
 
-use chrono::{Datelike, Local};
+pub use self::pattern::Pattern;
+pub use self::validate::{Issue, Level};
+
+use chrono::{DateTime, Datelike, Local, NaiveDate, TimeZone, Timelike, Utc, Weekday};
+use failure::Fail;
 use feed::Feed;
-use std::path::Path;
-use yaml_rust::{Yaml, YamlLoader};
+use serde::de::{self, Deserialize, Deserializer};
+use serde::{Serialize, Serializer};
+use serde_json;
+use serde_yaml;
+use statistics::ListenerStats;
+use std::path::{Path, PathBuf};
+use toml;
 
 #[derive(Fail, Debug)]
 pub enum ConfigError {
-    #[fail(display = "{}", _0)]
-    Io(#[cause] ::std::io::Error),
+    #[fail(display = "failed to read {}", _1)]
+    Io(#[cause] ::std::io::Error, String),
+
+    #[fail(display = "failed to parse {}", _1)]
+    Yaml(#[cause] serde_yaml::Error, String),
+
+    #[fail(display = "failed to parse {}", _1)]
+    Toml(#[cause] toml::de::Error, String),
+
+    #[fail(display = "failed to parse {}", _1)]
+    Json(#[cause] serde_json::Error, String),
+
+    #[fail(display = "failed to load {}", _1)]
+    Invalid(#[cause] serde_json::Error, String),
+
+    #[fail(display = "failed to write config as YAML")]
+    YamlOutput(#[cause] serde_yaml::Error),
+
+    #[fail(display = "failed to write config as TOML")]
+    TomlOutput(#[cause] toml::ser::Error),
+
+    #[fail(display = "failed to write config as JSON")]
+    JsonOutput(#[cause] serde_json::Error),
+}
+
+/// The formats that the config file can be written in.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Format {
+    Yaml,
+    Toml,
+    Json,
+}
+
+impl Format {
+    /// Picks the format of a config file by its extension. YAML is used when the extension isn't known.
+    pub fn of(path: &Path) -> Format {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("toml") => Format::Toml,
+            Some("json") => Format::Json,
+            _ => Format::Yaml,
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Format> {
+        match name {
+            "yaml" | "yml" => Some(Format::Yaml),
+            "toml" => Some(Format::Toml),
+            "json" => Some(Format::Json),
+            _ => None,
+        }
+    }
+}
+
+/// The names the config file can have, in the order they're looked for.
+const FILE_NAMES: [&str; 4] = ["config.yaml", "config.yml", "config.toml", "config.json"];
+
+/// Finds the config file in the specified directory.
+/// When there isn't one, the path of a YAML config file is returned.
+pub fn find_file(dir: &Path) -> PathBuf {
+    FILE_NAMES
+        .iter()
+        .map(|name| dir.join(name))
+        .find(|path| path.exists())
+        .unwrap_or_else(|| dir.join(FILE_NAMES[0]))
+}
+
+/// Removes every key without a value.
+fn remove_nulls(value: &mut serde_json::Value) {
+    match *value {
+        serde_json::Value::Object(ref mut fields) => {
+            let nulls = fields
+                .iter()
+                .filter(|&(_, value)| value.is_null())
+                .map(|(key, _)| key.clone())
+                .collect::<Vec<_>>();
+
+            for key in nulls {
+                fields.remove(&key);
+            }
+
+            for (_, value) in fields.iter_mut() {
+                remove_nulls(value);
+            }
+        }
+        serde_json::Value::Array(ref mut values) => {
+            for value in values {
+                remove_nulls(value);
+            }
+        }
+        _ => (),
+    }
+}
+
+/// The lowest and highest values that a setting can have. Values outside of them are raised or lowered
+/// to fit when the config is loaded, and they're written to the generated config file.
+#[derive(Debug)]
+pub struct Limit {
+    pub key: &'static str,
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+}
+
+impl Limit {
+    fn apply<T: Bounded>(&self, value: &mut T) {
+        value.clamp_to(self.min, self.max);
+    }
+}
+
+/// A setting that can be raised or lowered to fit inside of a limit.
+trait Bounded {
+    fn clamp_to(&mut self, min: Option<f64>, max: Option<f64>);
+}
+
+macro_rules! impl_bounded {
+    ($($t:ty,)+) => {
+        $(
+        impl Bounded for $t {
+            fn clamp_to(&mut self, min: Option<f64>, max: Option<f64>) {
+                if let Some(min) = min {
+                    if f64::from(*self) < min {
+                        *self = min as $t;
+                    }
+                }
+
+                if let Some(max) = max {
+                    if f64::from(*self) > max {
+                        *self = max as $t;
+                    }
+                }
+            }
+        }
+        )+
+    };
+}
+
+impl_bounded!(u8, u32, f32,);
+
+impl<T: Bounded> Bounded for Option<T> {
+    fn clamp_to(&mut self, min: Option<f64>, max: Option<f64>) {
+        if let Some(ref mut value) = *self {
+            value.clamp_to(min, max);
+        }
+    }
+}
+
+macro_rules! gen_limit {
+    ($key:expr, [min $min:expr]) => {
+        Limit {
+            key: $key,
+            min: Some($min),
+            max: None,
+        }
+    };
+    ($key:expr, [max $max:expr]) => {
+        Limit {
+            key: $key,
+            min: None,
+            max: Some($max),
+        }
+    };
+    ($key:expr, [min $min:expr, max $max:expr]) => {
+        Limit {
+            key: $key,
+            min: Some($min),
+            max: Some($max),
+        }
+    };
+}
+
+/// Generates the limits of a group of settings, along with a `clamp` method that applies them.
+macro_rules! limits {
+    ($name:ident, $($field:ident => $key:expr => $bounds:tt,)+) => {
+        impl $name {
+            const LIMITS: &'static [Limit] = &[$(gen_limit!($key, $bounds),)+];
 
-    #[fail(display = "YAML error: {}", _0)]
-    YAMLScan(#[cause] ::yaml_rust::ScanError),
+            fn clamp(&mut self) {
+                $(gen_limit!($key, $bounds).apply(&mut self.$field);)+
+            }
+        }
+    };
+}
+
+fn default_true() -> bool {
+    true
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Spike {
+    #[serde(rename = "Jump Required")]
+    pub jump: f32,
+    #[serde(rename = "Low Listener Increase")]
+    pub low_listener_increase: f32,
+    #[serde(rename = "High Listener Decrease")]
+    pub high_listener_dec: f32,
+    #[serde(rename = "High Listener Decrease Per Listeners")]
+    pub high_listener_dec_every: f32,
+    /// The fewest listeners a feed has to gain over its average to spike, regardless of the multiplier.
+    #[serde(rename = "Minimum Jump")]
+    pub minimum_jump: f32,
+    /// Feeds with fewer listeners than this use the low listener increase.
+    #[serde(rename = "Low Listener Cutoff")]
+    pub low_listener_cutoff: f32,
+    /// How much of the jump required a feed that is already spiking has to keep to stay spiking, so feeds
+    /// that hover around the jump required don't keep starting and ending incidents.
+    #[serde(rename = "Exit Jump Ratio")]
+    pub exit_jump_ratio: f32,
+}
+
+impl Default for Spike {
+    fn default() -> Spike {
+        Spike {
+            jump: 0.3,
+            low_listener_increase: 0.005,
+            high_listener_dec: 0.02,
+            high_listener_dec_every: 100.0,
+            minimum_jump: 0.0,
+            low_listener_cutoff: 50.0,
+            exit_jump_ratio: 1.0,
+        }
+    }
 }
 
-create_config_struct!(Spike,
-    jump:                    f32 => "Jump Required"                        => 0.3,
-    low_listener_increase:   f32 => "Low Listener Increase"                => [0.0, 0.005],
-    high_listener_dec:       f32 => "High Listener Decrease"               => [0.0, 0.02],
-    high_listener_dec_every: f32 => "High Listener Decrease Per Listeners" => [1.0, 100.0],
+limits!(Spike,
+    low_listener_increase   => "Low Listener Increase"                => [min 0.0],
+    high_listener_dec       => "High Listener Decrease"               => [min 0.0],
+    high_listener_dec_every => "High Listener Decrease Per Listeners" => [min 1.0],
+    minimum_jump            => "Minimum Jump"                         => [min 0.0],
+    low_listener_cutoff     => "Low Listener Cutoff"                  => [min 0.0],
+    exit_jump_ratio         => "Exit Jump Ratio"                      => [min 0.0, max 1.0],
 );
 
-create_config_struct!(UnskewedAverage,
-    reset_pcnt:      f32 => "Reset To Average Percentage"  => [0.0, 0.15],
-    adjust_pcnt:     f32 => "Adjust to Average Percentage" => [0.0, 0.0075],
-    spikes_required: u32 => "Spikes Required"              => 1,
-    jump_required:   f32 => "Jump Required To Set"         => [1.1, 4.0],
+/// Spike values that are layered on top of the global ones. Values that aren't set are taken from
+/// the layers below, so they're left out when the config is written.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SpikeOverride {
+    #[serde(rename = "Jump Required", skip_serializing_if = "Option::is_none")]
+    pub jump: Option<f32>,
+    #[serde(
+        rename = "Low Listener Increase",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub low_listener_increase: Option<f32>,
+    #[serde(
+        rename = "High Listener Decrease",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub high_listener_dec: Option<f32>,
+    #[serde(
+        rename = "High Listener Decrease Per Listeners",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub high_listener_dec_every: Option<f32>,
+    #[serde(rename = "Minimum Jump", skip_serializing_if = "Option::is_none")]
+    pub minimum_jump: Option<f32>,
+    #[serde(
+        rename = "Low Listener Cutoff",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub low_listener_cutoff: Option<f32>,
+    #[serde(rename = "Exit Jump Ratio", skip_serializing_if = "Option::is_none")]
+    pub exit_jump_ratio: Option<f32>,
+}
+
+impl SpikeOverride {
+    /// Replaces every value of the spike that is set.
+    pub fn apply_to(&self, spike: &mut Spike) {
+        if let Some(jump) = self.jump {
+            spike.jump = jump;
+        }
+
+        if let Some(increase) = self.low_listener_increase {
+            spike.low_listener_increase = increase;
+        }
+
+        if let Some(dec) = self.high_listener_dec {
+            spike.high_listener_dec = dec;
+        }
+
+        if let Some(every) = self.high_listener_dec_every {
+            spike.high_listener_dec_every = every;
+        }
+
+        if let Some(minimum) = self.minimum_jump {
+            spike.minimum_jump = minimum;
+        }
+
+        if let Some(cutoff) = self.low_listener_cutoff {
+            spike.low_listener_cutoff = cutoff;
+        }
+
+        if let Some(ratio) = self.exit_jump_ratio {
+            spike.exit_jump_ratio = ratio;
+        }
+    }
+}
+
+limits!(SpikeOverride,
+    low_listener_increase   => "Low Listener Increase"                => [min 0.0],
+    high_listener_dec       => "High Listener Decrease"               => [min 0.0],
+    high_listener_dec_every => "High Listener Decrease Per Listeners" => [min 1.0],
+    minimum_jump            => "Minimum Jump"                         => [min 0.0],
+    low_listener_cutoff     => "Low Listener Cutoff"                  => [min 0.0],
+    exit_jump_ratio         => "Exit Jump Ratio"                      => [min 0.0, max 1.0],
 );
 
-create_config_enum!(FeedIdent,
-    Name(String)   => self,
-    ID(u32)        => self,
-    County(String) => self,
-    State(u32)     => "State ID",
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UnskewedAverage {
+    #[serde(rename = "Reset To Average Percentage")]
+    pub reset_pcnt: f32,
+    #[serde(rename = "Adjust to Average Percentage")]
+    pub adjust_pcnt: f32,
+    #[serde(rename = "Spikes Required")]
+    pub spikes_required: u32,
+    #[serde(rename = "Jump Required To Set")]
+    pub jump_required: f32,
+}
+
+impl Default for UnskewedAverage {
+    fn default() -> UnskewedAverage {
+        UnskewedAverage {
+            reset_pcnt: 0.15,
+            adjust_pcnt: 0.0075,
+            spikes_required: 1,
+            jump_required: 4.0,
+        }
+    }
+}
+
+limits!(UnskewedAverage,
+    reset_pcnt    => "Reset To Average Percentage"  => [min 0.0],
+    adjust_pcnt   => "Adjust to Average Percentage" => [min 0.0],
+    jump_required => "Jump Required To Set"         => [min 1.1],
 );
 
+#[derive(Debug, Serialize, Deserialize)]
+pub enum FeedIdent {
+    Name(String),
+    ID(u32),
+    County(String),
+    #[serde(rename = "State ID")]
+    State(u32),
+    #[serde(rename = "Name Matches")]
+    NameGlob(#[serde(deserialize_with = "Pattern::deserialize_glob")] Pattern),
+    #[serde(rename = "Name Regex")]
+    NameRegex(#[serde(deserialize_with = "Pattern::deserialize_regex")] Pattern),
+    #[serde(rename = "County Matches")]
+    CountyGlob(#[serde(deserialize_with = "Pattern::deserialize_glob")] Pattern),
+    #[serde(rename = "County Regex")]
+    CountyRegex(#[serde(deserialize_with = "Pattern::deserialize_regex")] Pattern),
+    Listeners(ListenerRange),
+    All(Vec<FeedIdent>),
+    Any(Vec<FeedIdent>),
+    Not(Box<FeedIdent>),
+}
+
 impl FeedIdent {
+    /// Returns how specific the FeedIdent is, which decides the order that feed settings are layered in.
+    /// States come first, followed by counties, and then individual feeds.
+    pub fn specificity(&self) -> u32 {
+        match *self {
+            FeedIdent::State(_) | FeedIdent::Listeners(_) => 1,
+            FeedIdent::County(_) | FeedIdent::CountyGlob(_) | FeedIdent::CountyRegex(_) => 2,
+            FeedIdent::Name(_)
+            | FeedIdent::ID(_)
+            | FeedIdent::NameGlob(_)
+            | FeedIdent::NameRegex(_) => 3,
+            FeedIdent::All(ref idents) => {
+                idents.iter().map(FeedIdent::specificity).max().unwrap_or(0)
+            }
+            FeedIdent::Any(ref idents) => {
+                idents.iter().map(FeedIdent::specificity).min().unwrap_or(0)
+            }
+            FeedIdent::Not(_) => 0,
+        }
+    }
+
     /// Returns true if the FeedIdent matches the corresponding feed data.
     pub fn matches_feed(&self, feed: &Feed) -> bool {
         match *self {
@@ -44,25 +405,149 @@ impl FeedIdent {
             FeedIdent::ID(id) => id == feed.id,
             FeedIdent::County(ref c) => *c == feed.county,
             FeedIdent::State(id) => id == feed.state.id,
+            FeedIdent::NameGlob(ref pattern) | FeedIdent::NameRegex(ref pattern) => {
+                pattern.is_match(&feed.name)
+            }
+            FeedIdent::CountyGlob(ref pattern) | FeedIdent::CountyRegex(ref pattern) => {
+                pattern.is_match(&feed.county)
+            }
+            FeedIdent::Listeners(ref range) => range.contains(feed.listeners),
+            FeedIdent::All(ref idents) => idents.iter().all(|ident| ident.matches_feed(feed)),
+            FeedIdent::Any(ref idents) => idents.iter().any(|ident| ident.matches_feed(feed)),
+            FeedIdent::Not(ref ident) => !ident.matches_feed(feed),
+        }
+    }
+
+    /// Returns whether the FeedIdent matches a feed when only its ID is known, or None if deciding
+    /// that needs the rest of the feed's data.
+    pub fn matches_id(&self, id: u32) -> Option<bool> {
+        match *self {
+            FeedIdent::ID(ident_id) => Some(ident_id == id),
+            FeedIdent::All(ref idents) => {
+                let results = idents
+                    .iter()
+                    .map(|ident| ident.matches_id(id))
+                    .collect::<Vec<_>>();
+
+                if results.contains(&Some(false)) {
+                    Some(false)
+                } else if results.iter().all(Option::is_some) {
+                    Some(true)
+                } else {
+                    None
+                }
+            }
+            FeedIdent::Any(ref idents) => {
+                let results = idents
+                    .iter()
+                    .map(|ident| ident.matches_id(id))
+                    .collect::<Vec<_>>();
+
+                if results.contains(&Some(true)) {
+                    Some(true)
+                } else if results.iter().all(Option::is_some) {
+                    Some(false)
+                } else {
+                    None
+                }
+            }
+            FeedIdent::Not(ref ident) => ident.matches_id(id).map(|matches| !matches),
+            _ => None,
         }
     }
 }
 
-create_config_enum!(WeekdaySpike,
-    Sunday(Spike)    => self,
-    Monday(Spike)    => self,
-    Tuesday(Spike)   => self,
-    Wednesday(Spike) => self,
-    Thursday(Spike)  => self,
-    Friday(Spike)    => self,
-    Saturday(Spike)  => self,
-);
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BlacklistEntry {
+    #[serde(flatten)]
+    pub ident: FeedIdent,
+    /// The time the entry stops applying. It applies forever when it isn't set.
+    #[serde(rename = "Until", default, skip_serializing_if = "Option::is_none")]
+    pub until: Option<Timestamp>,
+}
+
+impl BlacklistEntry {
+    /// Returns true if the entry hasn't expired and matches the feed.
+    pub fn matches_feed(&self, feed: &Feed) -> bool {
+        let expired = match self.until {
+            Some(ref until) => Utc::now() >= until.time,
+            None => false,
+        };
+
+        !expired && self.ident.matches_feed(feed)
+    }
+}
+
+/// A time from the config file. It keeps the text it was written as, so it can be written back out the same way.
+#[derive(Debug)]
+pub struct Timestamp {
+    source: String,
+    pub time: DateTime<Utc>,
+}
+
+impl Timestamp {
+    pub fn parse(source: String) -> Option<Timestamp> {
+        let time = ::util::parse_time(&source)?;
+        Some(Timestamp { source, time })
+    }
+}
+
+impl Serialize for Timestamp {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(&self.source)
+    }
+}
+
+impl<'de> Deserialize<'de> for Timestamp {
+    fn deserialize<D>(deserializer: D) -> Result<Timestamp, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let source = String::deserialize(deserializer)?;
+        let msg = format!(
+            "invalid time \"{}\", expected one like \"2018-06-01 18:00\"",
+            source
+        );
+
+        Timestamp::parse(source).ok_or_else(|| de::Error::custom(msg))
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ListenerRange {
+    #[serde(rename = "Min", default)]
+    pub min: Option<u32>,
+    #[serde(rename = "Max", default)]
+    pub max: Option<u32>,
+}
+
+impl ListenerRange {
+    /// Returns true if the listeners are within the range. Both ends of the range are included.
+    pub fn contains(&self, listeners: u32) -> bool {
+        self.min.map(|min| listeners >= min).unwrap_or(true)
+            && self.max.map(|max| listeners <= max).unwrap_or(true)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub enum WeekdaySpike {
+    Sunday(SpikeOverride),
+    Monday(SpikeOverride),
+    Tuesday(SpikeOverride),
+    Wednesday(SpikeOverride),
+    Thursday(SpikeOverride),
+    Friday(SpikeOverride),
+    Saturday(SpikeOverride),
+}
 
 impl WeekdaySpike {
     /// Returns the spike values for the current day, if it exists in the specified array.
-    pub fn get_for_today(weekday_spikes: &[WeekdaySpike]) -> Option<&Spike> {
-        use chrono::Weekday::*;
+    pub fn get_for_today(weekday_spikes: &[WeekdaySpike]) -> Option<&SpikeOverride> {
         use self::WeekdaySpike::*;
+        use chrono::Weekday::*;
 
         let weekday = Local::today().weekday();
 
@@ -81,138 +566,1717 @@ impl WeekdaySpike {
 
         None
     }
-}
 
-create_config_struct!(FeedSetting,
-    ident:          FeedIdent         => self                        => fail,
-    spike:          Spike             => "Spike Percentages"         => default,
-    weekday_spikes: Vec<WeekdaySpike> => "Weekday Spike Percentages" => all,
-);
+    fn spike_mut(&mut self) -> &mut SpikeOverride {
+        use self::WeekdaySpike::*;
 
-create_config_struct!(Misc,
-	update_time:       f32         => "Update Time"              => [5.0, 6.0],
-	minimum_listeners: u32         => "Minimum Listeners"        => 15,
-	state_feeds_id:    Option<u32> => "State Feeds ID"           => None,
-    max_feeds:         u32         => "Maximum Feeds To Display" => 10,
-);
+        match *self {
+            Sunday(ref mut s) | Monday(ref mut s) | Tuesday(ref mut s) | Wednesday(ref mut s)
+            | Thursday(ref mut s) | Friday(ref mut s) | Saturday(ref mut s) => s,
+        }
+    }
+}
 
-create_config_enum!(SortType,
-    Listeners => self,
-    Jump      => self,
-);
+/// Spike values that apply during a range of hours.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TimeSpike {
+    #[serde(flatten)]
+    pub hours: HourRange,
+    #[serde(rename = "Spike Percentages")]
+    pub spike: SpikeOverride,
+}
 
-create_config_enum!(SortOrder,
-    Ascending  => self,
-    Descending => self,
-);
+impl TimeSpike {
+    fn clamp(&mut self) {
+        self.hours.clamp();
+        self.spike.clamp();
+    }
+}
 
-create_config_struct!(Sorting,
-    sort_type:  SortType  => "Sort By"    => { SortType::Listeners },
-    sort_order: SortOrder => "Sort Order" => { SortOrder::Descending },
-);
+/// A date from the config file. One without a year happens every year. It keeps the text it was written
+/// as, so it can be written back out the same way.
+#[derive(Debug)]
+pub struct ConfigDate {
+    source: String,
+    kind: DateKind,
+}
 
-macro_rules! gen_base_parse_stmt {
-    (optional, $category:expr, $doc:ident) => (ParseYaml::from(&$doc[$category]));
-    (default,  $category:expr, $doc:ident) => (ParseYaml::from_or_default(&$doc[$category]));
-    (all,      $category:expr, $doc:ident) => (ParseYaml::all(&$doc[$category]));
+#[derive(Debug)]
+enum DateKind {
+    Fixed(NaiveDate),
+    Yearly { month: u32, day: u32 },
 }
 
-macro_rules! gen_base_config {
-    ($name:ident, $($field:ident: $type:ty => $parse_type:ident => $category:expr,)+) => {
-        #[derive(Debug, Default)]
-        pub struct $name {
-            $(pub $field: $type,)+
-        }
+impl ConfigDate {
+    /// Parses a date like "2018-07-04", or one like "07-04" that happens every year.
+    pub fn parse(source: String) -> Option<ConfigDate> {
+        let source_str = source.trim();
 
-        impl $name {
-            pub fn from_file(path: &Path) -> Result<$name, ConfigError> {
-                let file = ::util::read_file(path).map_err(ConfigError::Io)?;
+        let kind = match NaiveDate::parse_from_str(source_str, "%Y-%m-%d") {
+            Ok(date) => DateKind::Fixed(date),
+            Err(_) => {
+                let mut parts = source_str.splitn(2, '-');
+                let month = parts.next()?.parse().ok()?;
+                let day = parts.next()?.parse().ok()?;
 
-                if file.len() == 0 {
-                    return Ok(Config::default())
-                }
+                // A leap year is used so February 29th is allowed
+                NaiveDate::from_ymd_opt(2000, month, day)?;
+                DateKind::Yearly { month, day }
+            }
+        };
 
-                let doc = YamlLoader::load_from_str(&file).map_err(ConfigError::YAMLScan)?;
-                let doc = &doc[0]; // We only care about the first document
+        Some(ConfigDate { source, kind })
+    }
 
-                Ok($name {
-                    $($field: gen_base_parse_stmt!($parse_type, $category, doc),)+
-                })
-            }
+    fn month_day(&self) -> (u32, u32) {
+        match self.kind {
+            DateKind::Fixed(date) => (date.month(), date.day()),
+            DateKind::Yearly { month, day } => (month, day),
         }
-    };
+    }
 }
 
-gen_base_config!(Config,
-    global_spike:   Spike             => default => "Spike Percentage",
-    unskewed_avg:   UnskewedAverage   => default => "Unskewed Average",
-    weekday_spikes: Vec<WeekdaySpike> => all     => "Weekday Spike Percentages",
-    feed_settings:  Vec<FeedSetting>  => all     => "Feed Settings",
-    misc:           Misc              => default => "Misc",
-    sorting:        Sorting           => default => "Feed Sorting",
-    blacklist:      Vec<FeedIdent>    => all     => "Blacklist",
-    whitelist:      Vec<FeedIdent>    => all     => "Whitelist",
-);
+impl Serialize for ConfigDate {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(&self.source)
+    }
+}
 
-impl Config {
-    /// Gets the spike values for the specified feed based off of
-    /// other configuration values that may be set.
-    pub fn get_feed_spike(&self, feed: &Feed) -> &Spike {
-        // Find any settings for the specified feed
-        let feed_setting = self.feed_settings
-            .iter()
-            .find(|s| s.ident.matches_feed(feed));
+impl<'de> Deserialize<'de> for ConfigDate {
+    fn deserialize<D>(deserializer: D) -> Result<ConfigDate, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let source = String::deserialize(deserializer)?;
+        let msg = format!(
+            "invalid date \"{}\", expected one like \"2018-07-04\", or \"07-04\" for every year",
+            source
+        );
 
-        match feed_setting {
-            Some(setting) => {
-                WeekdaySpike::get_for_today(&setting.weekday_spikes).unwrap_or(&setting.spike)
+        ConfigDate::parse(source).ok_or_else(|| de::Error::custom(msg))
+    }
+}
+
+/// Settings that apply on a date or range of dates, like holidays and special events.
+// The derived implementations are only used by the ones below, which check that the dates can be used together
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(remote = "DateOverride")]
+pub struct DateOverride {
+    #[serde(
+        rename = "Description",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub description: Option<String>,
+    #[serde(rename = "Date")]
+    pub date: ConfigDate,
+    /// The last day of the range, which is included in it. When it isn't set, only the date applies.
+    #[serde(rename = "End Date", default, skip_serializing_if = "Option::is_none")]
+    pub end_date: Option<ConfigDate>,
+    #[serde(rename = "Spike Percentages", default)]
+    pub spike: SpikeOverride,
+    #[serde(rename = "Suppress Notifications", default)]
+    pub suppress_notifications: bool,
+    /// Keeps the day's listeners out of the hourly averages, so an unusual day doesn't throw them off.
+    #[serde(rename = "Skip Learning", default)]
+    pub skip_learning: bool,
+}
+
+impl Serialize for DateOverride {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        DateOverride::serialize(self, serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for DateOverride {
+    fn deserialize<D>(deserializer: D) -> Result<DateOverride, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let entry = DateOverride::deserialize(deserializer)?;
+
+        if let Some(ref end_date) = entry.end_date {
+            match (&entry.date.kind, &end_date.kind) {
+                (&DateKind::Fixed(_), &DateKind::Yearly { .. })
+                | (&DateKind::Yearly { .. }, &DateKind::Fixed(_)) => {
+                    return Err(de::Error::custom(format!(
+                        "\"Date\" ({}) and \"End Date\" ({}) both need a year, or both need to leave it out",
+                        entry.date.source, end_date.source
+                    )))
+                }
+                _ => (),
             }
-            None => WeekdaySpike::get_for_today(&self.weekday_spikes).unwrap_or(&self.global_spike),
         }
+
+        Ok(entry)
     }
 }
 
-trait ParseYaml: Sized + Default {
-    fn from(doc: &Yaml) -> Option<Self>;
+impl DateOverride {
+    /// Returns true if the date is inside of the override's range. A range that happens every year
+    /// can wrap around the end of it.
+    pub fn contains(&self, date: NaiveDate) -> bool {
+        let end = self.end_date.as_ref().unwrap_or(&self.date);
 
-    fn from_or_default(doc: &Yaml) -> Self {
-        ParseYaml::from(doc).unwrap_or_default()
+        if let (&DateKind::Fixed(start), &DateKind::Fixed(end)) = (&self.date.kind, &end.kind) {
+            return start <= date && date <= end;
+        }
+
+        let start = self.date.month_day();
+        let end = end.month_day();
+        let date = (date.month(), date.day());
+
+        if start <= end {
+            start <= date && date <= end
+        } else {
+            date >= start || date <= end
+        }
     }
 
-    fn all(doc: &Yaml) -> Vec<Self> {
-        doc.as_vec()
-            .unwrap_or(&Vec::new())
+    /// Returns the overrides that apply today, along with their indices.
+    pub fn get_for_today(overrides: &[DateOverride]) -> Vec<(usize, &DateOverride)> {
+        let today = Local::today().naive_local();
+
+        overrides
             .iter()
-            .filter_map(ParseYaml::from)
+            .enumerate()
+            .filter(|(_, entry)| entry.contains(today))
             .collect()
     }
 }
 
-macro_rules! impl_parseyaml_for_numeric {
-    ($($t:ty )+) => {
-        $(
-        impl ParseYaml for $t {
-            fn from(doc: &Yaml) -> Option<$t> {
-                use yaml_rust::Yaml::*;
-                match *doc {
-                    Integer(num)     => Some(num as $t),
-                    Real(ref string) => string.parse().ok(),
-                    _                => None,
-                }
-            }
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FeedSetting {
+    #[serde(flatten)]
+    pub ident: FeedIdent,
+    /// Feed settings with a higher priority are layered on top of ones with a lower priority. When it
+    /// isn't set, it's the specificity of the ident.
+    #[serde(rename = "Priority", default)]
+    pub priority: Option<u32>,
+    #[serde(rename = "Spike Percentages", default)]
+    pub spike: SpikeOverride,
+    #[serde(rename = "Weekday Spike Percentages", default)]
+    pub weekday_spikes: Vec<WeekdaySpike>,
+    #[serde(rename = "Time Spike Percentages", default)]
+    pub time_spikes: Vec<TimeSpike>,
+    #[serde(rename = "Date Overrides", default)]
+    pub date_overrides: Vec<DateOverride>,
+}
+
+impl FeedSetting {
+    pub fn priority(&self) -> u32 {
+        self.priority.unwrap_or_else(|| self.ident.specificity())
+    }
+}
+
+/// A set of spike values that applies to a feed, and where it's from in the config.
+pub struct SpikeLayer<'a> {
+    pub source: String,
+    pub spike: &'a SpikeOverride,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Misc {
+    #[serde(rename = "Update Time")]
+    pub update_time: f32,
+    #[serde(rename = "Minimum Listeners")]
+    pub minimum_listeners: u32,
+    #[serde(rename = "State Feeds ID")]
+    pub state_feeds_id: Option<u32>,
+    #[serde(rename = "Maximum Feeds To Display")]
+    pub max_feeds: u32,
+    /// The most feeds from one state that can be displayed in an update.
+    #[serde(rename = "Maximum Feeds Per State")]
+    pub max_feeds_per_state: Option<u32>,
+    /// The most feeds from one county that can be displayed in an update.
+    #[serde(rename = "Maximum Feeds Per County")]
+    pub max_feeds_per_county: Option<u32>,
+    #[serde(rename = "Display Mode")]
+    pub display_mode: DisplayMode,
+    #[serde(rename = "Notify When Incident Ends")]
+    pub show_incident_end: bool,
+    #[serde(rename = "Update Notifications In Place")]
+    pub update_in_place: bool,
+}
+
+impl Default for Misc {
+    fn default() -> Misc {
+        Misc {
+            update_time: 6.0,
+            minimum_listeners: 15,
+            state_feeds_id: None,
+            max_feeds: 10,
+            max_feeds_per_state: None,
+            max_feeds_per_county: None,
+            display_mode: DisplayMode::Individual,
+            show_incident_end: false,
+            update_in_place: true,
         }
-        )+
     }
 }
 
-impl_parseyaml_for_numeric!(u8 u32 f32);
+limits!(Misc,
+    update_time => "Update Time" => [min 5.0],
+);
 
-impl ParseYaml for String {
-    fn from(doc: &Yaml) -> Option<String> {
-        use yaml_rust::Yaml::*;
-        match *doc {
-            String(ref s) => Some(s.clone()),
-            _ => None,
+#[derive(Debug, Copy, Clone, PartialEq, PartialOrd, Serialize, Deserialize)]
+pub enum DisplayMode {
+    Individual,
+    Summary,
+    #[serde(rename = "By State")]
+    ByState,
+    #[serde(rename = "By County")]
+    ByCounty,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, PartialOrd, Serialize, Deserialize)]
+pub enum SortType {
+    Listeners,
+    Jump,
+    #[serde(rename = "Jump Percentage")]
+    JumpPercent,
+    Severity,
+    #[serde(rename = "Spike Count")]
+    SpikeCount,
+    Alert,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, PartialOrd, Serialize, Deserialize)]
+pub enum SortOrder {
+    Ascending,
+    Descending,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Sorting {
+    /// Feeds are sorted by the first type, and each type after it breaks ties in the ones before it.
+    #[serde(rename = "Sort By", deserialize_with = "deserialize_sort_types")]
+    pub sort_types: Vec<SortType>,
+    #[serde(rename = "Sort Order")]
+    pub sort_order: SortOrder,
+}
+
+impl Default for Sorting {
+    fn default() -> Sorting {
+        Sorting {
+            sort_types: vec![SortType::Listeners],
+            sort_order: SortOrder::Descending,
+        }
+    }
+}
+
+/// Reads either a single sort type, or a list of them.
+fn deserialize_sort_types<'de, D>(deserializer: D) -> Result<Vec<SortType>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    use serde::de::value::SeqAccessDeserializer;
+    use serde::de::{IntoDeserializer, SeqAccess, Visitor};
+    use std::fmt;
+
+    struct SortTypes;
+
+    impl<'de> Visitor<'de> for SortTypes {
+        type Value = Vec<SortType>;
+
+        fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            f.write_str("a sort type or a list of them")
+        }
+
+        fn visit_str<E>(self, value: &str) -> Result<Vec<SortType>, E>
+        where
+            E: de::Error,
+        {
+            let sort_type = SortType::deserialize(value.into_deserializer())?;
+            Ok(vec![sort_type])
+        }
+
+        fn visit_seq<A>(self, seq: A) -> Result<Vec<SortType>, A::Error>
+        where
+            A: SeqAccess<'de>,
+        {
+            Vec::deserialize(SeqAccessDeserializer::new(seq))
+        }
+    }
+
+    deserializer.deserialize_any(SortTypes)
+}
+
+fn default_smtp_port() -> u16 {
+    587
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Email {
+    #[serde(rename = "SMTP Host")]
+    pub host: String,
+    #[serde(rename = "SMTP Port", default = "default_smtp_port")]
+    pub port: u16,
+    #[serde(rename = "Use STARTTLS", default = "default_true")]
+    pub starttls: bool,
+    #[serde(rename = "Username", default)]
+    pub username: Option<String>,
+    /// Listed in `SECRET_SETTINGS`, so its value isn't printed when it changes.
+    #[serde(rename = "Password", default)]
+    pub password: Option<String>,
+    #[serde(rename = "From")]
+    pub from: String,
+    #[serde(rename = "To", default)]
+    pub to: Vec<String>,
+    #[serde(rename = "Digest Minutes", default)]
+    pub digest_minutes: Option<f32>,
+}
+
+limits!(Email,
+    digest_minutes => "Digest Minutes" => [min 1.0],
+);
+
+fn default_exec_timeout() -> f32 {
+    10.0
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Exec {
+    #[serde(rename = "Command")]
+    pub command: String,
+    #[serde(rename = "Arguments", default)]
+    pub args: Vec<String>,
+    #[serde(rename = "Timeout Seconds", default = "default_exec_timeout")]
+    pub timeout: f32,
+}
+
+limits!(Exec,
+    timeout => "Timeout Seconds" => [min 1.0],
+);
+
+fn default_mqtt_port() -> u16 {
+    1883
+}
+
+fn default_mqtt_name() -> String {
+    "bcnotif".to_string()
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Mqtt {
+    #[serde(rename = "Host")]
+    pub host: String,
+    #[serde(rename = "Port", default = "default_mqtt_port")]
+    pub port: u16,
+    #[serde(rename = "Client ID", default = "default_mqtt_name")]
+    pub client_id: String,
+    #[serde(rename = "Username", default)]
+    pub username: Option<String>,
+    /// Listed in `SECRET_SETTINGS`, so its value isn't printed when it changes.
+    #[serde(rename = "Password", default)]
+    pub password: Option<String>,
+    #[serde(rename = "Topic Prefix", default = "default_mqtt_name")]
+    pub topic_prefix: String,
+    #[serde(rename = "QoS", default)]
+    pub qos: u8,
+    #[serde(rename = "Retain", default)]
+    pub retain: bool,
+    #[serde(rename = "Publish All Feed Stats", default)]
+    pub publish_stats: bool,
+}
+
+limits!(Mqtt,
+    qos => "QoS" => [max 2.0],
+);
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Actions {
+    #[serde(rename = "Enabled")]
+    pub enabled: bool,
+    #[serde(rename = "Open Command")]
+    pub open_command: String,
+    #[serde(rename = "Open Arguments")]
+    pub open_args: Vec<String>,
+    #[serde(rename = "Temporary Mute Hours")]
+    pub mute_hours: f32,
+}
+
+impl Default for Actions {
+    fn default() -> Actions {
+        Actions {
+            enabled: true,
+            open_command: "xdg-open".to_string(),
+            open_args: Vec::new(),
+            mute_hours: 1.0,
         }
     }
 }
+
+limits!(Actions,
+    mute_hours => "Temporary Mute Hours" => [min 0.1],
+);
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum Severity {
+    Minor,
+    Major,
+    Critical,
+}
+
+impl Severity {
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Severity::Minor => "minor",
+            Severity::Major => "major",
+            Severity::Critical => "critical",
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, PartialOrd, Serialize, Deserialize)]
+pub enum Urgency {
+    Low,
+    Normal,
+    Critical,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SeverityStyle {
+    #[serde(rename = "Urgency")]
+    pub urgency: Option<Urgency>,
+    #[serde(rename = "Timeout Seconds")]
+    pub timeout: Option<f32>,
+    #[serde(rename = "Icon")]
+    pub icon: Option<String>,
+    #[serde(rename = "Sound")]
+    pub sound: Option<String>,
+}
+
+limits!(SeverityStyle,
+    timeout => "Timeout Seconds" => [min 0.0],
+);
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Severities {
+    #[serde(rename = "Major Jump Ratio")]
+    pub major_jump: f32,
+    #[serde(rename = "Major Listeners")]
+    pub major_listeners: u32,
+    #[serde(rename = "Critical Jump Ratio")]
+    pub critical_jump: f32,
+    #[serde(rename = "Critical Listeners")]
+    pub critical_listeners: u32,
+    #[serde(rename = "Alert Severity")]
+    pub alert_severity: Severity,
+    #[serde(rename = "Minor")]
+    pub minor: SeverityStyle,
+    #[serde(rename = "Major")]
+    pub major: SeverityStyle,
+    #[serde(rename = "Critical")]
+    pub critical: SeverityStyle,
+}
+
+impl Default for Severities {
+    fn default() -> Severities {
+        Severities {
+            major_jump: 1.0,
+            major_listeners: 500,
+            critical_jump: 3.0,
+            critical_listeners: 1500,
+            alert_severity: Severity::Major,
+            minor: SeverityStyle::default(),
+            major: SeverityStyle::default(),
+            critical: SeverityStyle::default(),
+        }
+    }
+}
+
+impl Severities {
+    /// Returns the severity of a feed update based off of how much the feed jumped
+    /// relative to its average, its total listeners, and whether or not it has an alert.
+    pub fn get(&self, feed: &Feed, stats: &ListenerStats) -> Severity {
+        let jump_ratio = stats.get_jump_pcnt(feed.listeners) / 100.0;
+
+        let mut severity =
+            if jump_ratio >= self.critical_jump || feed.listeners >= self.critical_listeners {
+                Severity::Critical
+            } else if jump_ratio >= self.major_jump || feed.listeners >= self.major_listeners {
+                Severity::Major
+            } else {
+                Severity::Minor
+            };
+
+        if feed.alert.is_some() && self.alert_severity > severity {
+            severity = self.alert_severity;
+        }
+
+        severity
+    }
+}
+
+limits!(Severities,
+    major_jump    => "Major Jump Ratio"    => [min 0.0],
+    critical_jump => "Critical Jump Ratio" => [min 0.0],
+);
+
+/// The notification backends that can be enabled by a schedule, and that templates can be specified for.
+#[derive(Debug, Copy, Clone, PartialEq, PartialOrd, Serialize, Deserialize)]
+pub enum Backend {
+    Desktop,
+    Email,
+    Exec,
+    #[serde(rename = "MQTT")]
+    Mqtt,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, PartialOrd, Serialize, Deserialize)]
+pub enum Day {
+    Sunday,
+    Monday,
+    Tuesday,
+    Wednesday,
+    Thursday,
+    Friday,
+    Saturday,
+}
+
+impl Day {
+    pub fn weekday(self) -> Weekday {
+        match self {
+            Day::Sunday => Weekday::Sun,
+            Day::Monday => Weekday::Mon,
+            Day::Tuesday => Weekday::Tue,
+            Day::Wednesday => Weekday::Wed,
+            Day::Thursday => Weekday::Thu,
+            Day::Friday => Weekday::Fri,
+            Day::Saturday => Weekday::Sat,
+        }
+    }
+}
+
+/// A range of hours, optionally only on certain days of the week.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HourRange {
+    #[serde(rename = "Days")]
+    pub days: Vec<Day>,
+    #[serde(rename = "Start Hour")]
+    pub start_hour: u32,
+    #[serde(rename = "End Hour")]
+    pub end_hour: u32,
+}
+
+impl HourRange {
+    /// Returns true if the specified time is inside of the range.
+    /// Ranges that end before they start wrap around midnight, and the hours after midnight
+    /// belong to the day the range started on.
+    pub fn contains<Tz: TimeZone>(&self, time: &DateTime<Tz>) -> bool {
+        let hour = time.hour();
+        let today = time.weekday();
+
+        let on_day = |weekday: Weekday| {
+            self.days.is_empty() || self.days.iter().any(|day| day.weekday() == weekday)
+        };
+
+        if self.start_hour == self.end_hour {
+            on_day(today)
+        } else if self.start_hour < self.end_hour {
+            on_day(today) && hour >= self.start_hour && hour < self.end_hour
+        } else {
+            (hour >= self.start_hour && on_day(today))
+                || (hour < self.end_hour && on_day(today.pred()))
+        }
+    }
+}
+
+limits!(HourRange,
+    start_hour => "Start Hour" => [max 23.0],
+    end_hour   => "End Hour"   => [max 24.0],
+);
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ScheduleWindow {
+    #[serde(flatten)]
+    pub hours: HourRange,
+    #[serde(rename = "Minimum Severity")]
+    pub min_severity: Severity,
+    #[serde(rename = "Backends")]
+    pub backends: Vec<Backend>,
+    #[serde(rename = "Summarize Suppressed")]
+    pub summarize: bool,
+}
+
+impl Default for ScheduleWindow {
+    fn default() -> ScheduleWindow {
+        ScheduleWindow {
+            hours: HourRange::default(),
+            min_severity: Severity::Minor,
+            backends: Vec::new(),
+            summarize: false,
+        }
+    }
+}
+
+impl ScheduleWindow {
+    /// Returns true if notifications can be sent through the specified backend during the window.
+    pub fn allows(&self, backend: Backend) -> bool {
+        self.backends.is_empty() || self.backends.contains(&backend)
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Template {
+    #[serde(rename = "Title")]
+    pub title: Option<String>,
+    #[serde(rename = "Body")]
+    pub body: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ErrorNotifications {
+    #[serde(rename = "Remind After Failures")]
+    pub reminder_after: u32,
+    #[serde(rename = "Notify When Recovered")]
+    pub notify_recovered: bool,
+}
+
+impl Default for ErrorNotifications {
+    fn default() -> ErrorNotifications {
+        ErrorNotifications {
+            reminder_after: 10,
+            notify_recovered: true,
+        }
+    }
+}
+
+limits!(ErrorNotifications,
+    reminder_after => "Remind After Failures" => [min 1.0],
+);
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Retries {
+    #[serde(rename = "Initial Delay Minutes")]
+    pub initial_minutes: f32,
+    #[serde(rename = "Maximum Delay Minutes")]
+    pub max_minutes: f32,
+    #[serde(rename = "Maximum Attempts")]
+    pub max_attempts: u32,
+    /// Desktop notifications and commands are dropped once they're this old, since they're about what a feed is doing now.
+    #[serde(rename = "Maximum Age Minutes")]
+    pub max_age_minutes: f32,
+}
+
+impl Default for Retries {
+    fn default() -> Retries {
+        Retries {
+            initial_minutes: 5.0,
+            max_minutes: 120.0,
+            max_attempts: 10,
+            max_age_minutes: 30.0,
+        }
+    }
+}
+
+limits!(Retries,
+    initial_minutes => "Initial Delay Minutes" => [min 0.0],
+    max_minutes     => "Maximum Delay Minutes" => [min 0.0],
+    max_age_minutes => "Maximum Age Minutes"   => [min 0.0],
+);
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Templates {
+    #[serde(rename = "Spike")]
+    pub spike: Template,
+    #[serde(rename = "Alert")]
+    pub alert: Template,
+    #[serde(rename = "Incident Ended")]
+    pub incident_ended: Template,
+    #[serde(rename = "Error")]
+    pub error: Template,
+    #[serde(rename = "Recovered")]
+    pub recovered: Template,
+    #[serde(rename = "Summary Title")]
+    pub summary_title: Option<String>,
+    #[serde(rename = "Summary Line")]
+    pub summary_line: Option<String>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BackendTemplates {
+    #[serde(rename = "Desktop")]
+    pub desktop: Templates,
+    #[serde(rename = "Email")]
+    pub email: Templates,
+    #[serde(rename = "Exec")]
+    pub exec: Templates,
+    #[serde(rename = "MQTT")]
+    pub mqtt: Templates,
+}
+
+/// Filters for which feeds statistics are kept for. They're separate from the filters for which feeds can
+/// show notifications, so a feed that starts being allowed to notify already has accurate statistics.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Tracking {
+    #[serde(rename = "Minimum Listeners")]
+    pub minimum_listeners: u32,
+    #[serde(rename = "Blacklist")]
+    pub blacklist: Vec<BlacklistEntry>,
+    #[serde(rename = "Whitelist")]
+    pub whitelist: Vec<FeedIdent>,
+}
+
+/// Returns true if the feed has enough listeners, matches the whitelist (if it has any entries), and
+/// doesn't match the blacklist.
+fn passes_filters(
+    feed: &Feed,
+    minimum_listeners: u32,
+    blacklist: &[BlacklistEntry],
+    whitelist: &[FeedIdent],
+) -> bool {
+    if feed.listeners < minimum_listeners {
+        return false;
+    }
+
+    if !whitelist.is_empty() && !whitelist.iter().any(|entry| entry.matches_feed(feed)) {
+        return false;
+    }
+
+    !blacklist.iter().any(|entry| entry.matches_feed(feed))
+}
+
+/// Adds the weekday, time of day, and date spike values that apply right now, in the order they're applied
+/// in. Times of day are more specific than weekdays, and dates are for unusual days, so they take
+/// precedence in that order.
+fn push_timed_layers<'a>(
+    layers: &mut Vec<SpikeLayer<'a>>,
+    source: Option<&str>,
+    weekday_spikes: &'a [WeekdaySpike],
+    time_spikes: &'a [TimeSpike],
+    date_overrides: &'a [DateOverride],
+) {
+    let name = |category: &str| match source {
+        Some(source) => format!("{} > {}", source, category),
+        None => category.to_string(),
+    };
+
+    if let Some(spike) = WeekdaySpike::get_for_today(weekday_spikes) {
+        layers.push(SpikeLayer {
+            source: name("Weekday Spike Percentages"),
+            spike,
+        });
+    }
+
+    let now = Local::now();
+
+    // The first time that contains the current one takes precedence, so they're applied last to first
+    for (i, time) in time_spikes.iter().enumerate().rev() {
+        if time.hours.contains(&now) {
+            layers.push(SpikeLayer {
+                source: format!("{} #{}", name("Time Spike Percentages"), i + 1),
+                spike: &time.spike,
+            });
+        }
+    }
+
+    // The first date that contains today takes precedence too
+    for (i, entry) in DateOverride::get_for_today(date_overrides)
+        .into_iter()
+        .rev()
+    {
+        layers.push(SpikeLayer {
+            source: format!("{} #{}", name("Date Overrides"), i + 1),
+            spike: &entry.spike,
+        });
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    #[serde(rename = "Spike Percentage")]
+    pub global_spike: Spike,
+    #[serde(rename = "Unskewed Average")]
+    pub unskewed_avg: UnskewedAverage,
+    #[serde(rename = "Weekday Spike Percentages")]
+    pub weekday_spikes: Vec<WeekdaySpike>,
+    #[serde(rename = "Time Spike Percentages")]
+    pub time_spikes: Vec<TimeSpike>,
+    #[serde(rename = "Date Overrides")]
+    pub date_overrides: Vec<DateOverride>,
+    #[serde(rename = "Feed Settings")]
+    pub feed_settings: Vec<FeedSetting>,
+    #[serde(rename = "Misc")]
+    pub misc: Misc,
+    #[serde(rename = "Feed Sorting")]
+    pub sorting: Sorting,
+    #[serde(rename = "Blacklist")]
+    pub blacklist: Vec<BlacklistEntry>,
+    #[serde(rename = "Whitelist")]
+    pub whitelist: Vec<FeedIdent>,
+    #[serde(rename = "Tracking")]
+    pub tracking: Tracking,
+    #[serde(rename = "Email")]
+    pub email: Option<Email>,
+    #[serde(rename = "Exec")]
+    pub exec: Vec<Exec>,
+    #[serde(rename = "MQTT")]
+    pub mqtt: Option<Mqtt>,
+    #[serde(rename = "Templates")]
+    pub templates: BackendTemplates,
+    #[serde(rename = "Severity")]
+    pub severity: Severities,
+    #[serde(rename = "Notification Actions")]
+    pub actions: Actions,
+    #[serde(rename = "Schedule")]
+    pub schedule: Vec<ScheduleWindow>,
+    #[serde(rename = "Error Notifications")]
+    pub errors: ErrorNotifications,
+    #[serde(rename = "Notification Retries")]
+    pub retries: Retries,
+}
+
+impl Config {
+    /// Loads the config file in the format that its extension specifies.
+    pub fn from_file(path: &Path) -> Result<Config, ConfigError> {
+        let display_path = path.display().to_string();
+
+        let file = ::util::read_file(path).map_err(|e| ConfigError::Io(e, display_path.clone()))?;
+
+        Config::parse(&file, Format::of(path), &display_path).map(|(config, _)| config)
+    }
+
+    /// Loads the config and finds every problem in it that didn't stop it from loading.
+    /// Values that can't be read are reported and left out, so their settings use the default value.
+    fn parse(
+        contents: &str,
+        format: Format,
+        display_path: &str,
+    ) -> Result<(Config, Vec<Issue>), ConfigError> {
+        let mut value = load::read(contents, format, display_path)?;
+        let mut problems = Vec::new();
+
+        let mut config = load::deserialize(&mut value, &mut problems)
+            .map_err(|e| ConfigError::Invalid(e, display_path.into()))?;
+
+        config.clamp();
+
+        // Finding a position means reading the file again, so it's only done for settings with a problem
+        let locate = |path: &[load::Key]| load::position(contents, format, path);
+
+        let mut issues = problems
+            .into_iter()
+            .map(|(path, msg)| Issue::new(Level::Error, locate(&path), msg))
+            .collect::<Vec<_>>();
+
+        issues.extend(validate::validate(&value, &config, locate));
+        issues.sort_by_key(|issue| issue.location);
+
+        Ok((config, issues))
+    }
+
+    /// Checks the config file for unknown keys and invalid values without loading it.
+    pub fn validate_file(path: &Path) -> Result<Vec<Issue>, ConfigError> {
+        let display_path = path.display().to_string();
+
+        let file = ::util::read_file(path).map_err(|e| ConfigError::Io(e, display_path.clone()))?;
+
+        match Config::parse(&file, Format::of(path), &display_path) {
+            Ok((_, issues)) => Ok(issues),
+            Err(err) => {
+                // The cause has the position of the error, so it's more useful than the error itself
+                let msg = match err.cause() {
+                    Some(cause) => cause.to_string(),
+                    None => err.to_string(),
+                };
+
+                Ok(vec![Issue::new(Level::Error, None, msg)])
+            }
+        }
+    }
+
+    /// Writes the config in the specified format, with every default value filled in.
+    pub fn dump(&self, format: Format) -> Result<String, ConfigError> {
+        // TOML can't write enums with values without going through JSON values
+        let mut value = self.to_json()?;
+
+        match format {
+            Format::Yaml => serde_yaml::to_string(&value).map_err(ConfigError::YamlOutput),
+            Format::Toml => {
+                // TOML doesn't have a way to write missing values
+                remove_nulls(&mut value);
+
+                toml::Value::try_from(value)
+                    .and_then(|value| toml::to_string_pretty(&value))
+                    .map_err(ConfigError::TomlOutput)
+            }
+            Format::Json => serde_json::to_string_pretty(&value).map_err(ConfigError::JsonOutput),
+        }
+    }
+
+    /// Converts the config to JSON values, in the same order as the fields.
+    fn to_json(&self) -> Result<serde_json::Value, ConfigError> {
+        // Floats are stored as f32, so they're written to JSON text first to keep them from picking up
+        // extra digits when they're widened
+        serde_json::to_string(self)
+            .and_then(|json| serde_json::from_str(&json))
+            .map_err(ConfigError::JsonOutput)
+    }
+
+    /// Raises or lowers every value that is outside of the range it's allowed to be in.
+    fn clamp(&mut self) {
+        self.global_spike.clamp();
+        self.unskewed_avg.clamp();
+
+        for spike in &mut self.weekday_spikes {
+            spike.spike_mut().clamp();
+        }
+
+        for spike in &mut self.time_spikes {
+            spike.clamp();
+        }
+
+        for entry in &mut self.date_overrides {
+            entry.spike.clamp();
+        }
+
+        for setting in &mut self.feed_settings {
+            setting.spike.clamp();
+
+            for spike in &mut setting.weekday_spikes {
+                spike.spike_mut().clamp();
+            }
+
+            for spike in &mut setting.time_spikes {
+                spike.clamp();
+            }
+
+            for entry in &mut setting.date_overrides {
+                entry.spike.clamp();
+            }
+        }
+
+        self.misc.clamp();
+
+        if let Some(ref mut email) = self.email {
+            email.clamp();
+        }
+
+        for exec in &mut self.exec {
+            exec.clamp();
+        }
+
+        if let Some(ref mut mqtt) = self.mqtt {
+            mqtt.clamp();
+        }
+
+        self.severity.clamp();
+        self.severity.minor.clamp();
+        self.severity.major.clamp();
+        self.severity.critical.clamp();
+        self.actions.clamp();
+
+        for window in &mut self.schedule {
+            window.hours.clamp();
+        }
+
+        self.errors.clamp();
+        self.retries.clamp();
+    }
+
+    /// Returns true if statistics should be kept for the specified feed.
+    pub fn is_tracked(&self, feed: &Feed) -> bool {
+        let tracking = &self.tracking;
+        passes_filters(
+            feed,
+            tracking.minimum_listeners,
+            &tracking.blacklist,
+            &tracking.whitelist,
+        )
+    }
+
+    /// Returns true if the specified feed is allowed to show notifications. It also has to be tracked to
+    /// have the statistics needed to notify.
+    pub fn can_notify(&self, feed: &Feed) -> bool {
+        passes_filters(
+            feed,
+            self.misc.minimum_listeners,
+            &self.blacklist,
+            &self.whitelist,
+        )
+    }
+
+    /// Returns true if a date override that applies to the specified feed today suppresses its notifications.
+    pub fn notifications_suppressed(&self, feed: &Feed) -> bool {
+        self.get_date_overrides(feed)
+            .iter()
+            .any(|entry| entry.suppress_notifications)
+    }
+
+    /// Returns true if a date override that applies to the specified feed today keeps its listeners out
+    /// of the hourly averages.
+    pub fn skips_learning(&self, feed: &Feed) -> bool {
+        self.get_date_overrides(feed)
+            .iter()
+            .any(|entry| entry.skip_learning)
+    }
+
+    /// Gets every date override that applies to the specified feed today, from the global ones and the
+    /// feed settings that match it.
+    pub fn get_date_overrides(&self, feed: &Feed) -> Vec<&DateOverride> {
+        let mut overrides = DateOverride::get_for_today(&self.date_overrides);
+
+        for setting in &self.feed_settings {
+            if setting.ident.matches_feed(feed) {
+                overrides.extend(DateOverride::get_for_today(&setting.date_overrides));
+            }
+        }
+
+        overrides.into_iter().map(|(_, entry)| entry).collect()
+    }
+
+    /// Gets the spike values for the specified feed by layering every set of spike values that applies
+    /// to it on top of the global ones.
+    pub fn get_feed_spike(&self, feed: &Feed) -> Spike {
+        self.spike_from_layers(&self.get_spike_layers(feed))
+    }
+
+    /// Gets every set of spike values that applies to the specified feed, in the order they're applied in.
+    pub fn get_spike_layers<'a>(&'a self, feed: &Feed) -> Vec<SpikeLayer<'a>> {
+        self.spike_layers_where(|ident| ident.matches_feed(feed))
+    }
+
+    /// Gets the spike layers that apply to a feed when only its ID is known, along with the sources of
+    /// the feed settings that can't be matched without the rest of its data.
+    pub fn get_id_spike_layers<'a>(&'a self, id: u32) -> (Vec<SpikeLayer<'a>>, Vec<String>) {
+        let layers = self.spike_layers_where(|ident| ident.matches_id(id) == Some(true));
+
+        let unknown = self
+            .feed_settings
+            .iter()
+            .enumerate()
+            .filter(|(_, setting)| setting.ident.matches_id(id).is_none())
+            .map(|(i, setting)| {
+                format!("Feed Settings #{} (priority {})", i + 1, setting.priority())
+            })
+            .collect();
+
+        (layers, unknown)
+    }
+
+    /// Applies spike layers on top of the global spike values.
+    pub fn spike_from_layers(&self, layers: &[SpikeLayer]) -> Spike {
+        let mut spike = self.global_spike.clone();
+
+        for layer in layers {
+            layer.spike.apply_to(&mut spike);
+        }
+
+        spike
+    }
+
+    fn spike_layers_where<'a, F>(&'a self, matches: F) -> Vec<SpikeLayer<'a>>
+    where
+        F: Fn(&FeedIdent) -> bool,
+    {
+        let mut layers = Vec::new();
+
+        push_timed_layers(
+            &mut layers,
+            None,
+            &self.weekday_spikes,
+            &self.time_spikes,
+            &self.date_overrides,
+        );
+
+        let mut settings = self
+            .feed_settings
+            .iter()
+            .enumerate()
+            .filter(|(_, setting)| matches(&setting.ident))
+            .collect::<Vec<_>>();
+
+        // Settings with the same priority are applied last to first, so the first one still takes
+        // precedence like it did before settings were layered
+        settings.reverse();
+        settings.sort_by_key(|(_, setting)| setting.priority());
+
+        for (i, setting) in settings {
+            let source = format!("Feed Settings #{} (priority {})", i + 1, setting.priority());
+
+            layers.push(SpikeLayer {
+                source: source.clone(),
+                spike: &setting.spike,
+            });
+
+            push_timed_layers(
+                &mut layers,
+                Some(&source),
+                &setting.weekday_spikes,
+                &setting.time_spikes,
+                &setting.date_overrides,
+            );
+        }
+
+        layers
+    }
+
+    /// Gets the first schedule window that the current time is inside of, along with its index.
+    pub fn get_schedule_window(&self) -> Option<(usize, &ScheduleWindow)> {
+        let now = Local::now();
+
+        self.schedule
+            .iter()
+            .enumerate()
+            .find(|(_, window)| window.hours.contains(&now))
+    }
+}
+
+/// Keeps track of the config file across updates so a broken file doesn't stop the program.
+pub struct LiveConfig {
+    pub path: PathBuf,
+    /// The last config that was loaded successfully.
+    pub current: Config,
+    /// The last error that was reported, so the same error isn't reported on every update.
+    last_error: Option<String>,
+    /// The problems that were last found in the file, so they're only printed when they change.
+    last_issues: Vec<String>,
+    /// Whether the file has been read before, so changed settings aren't printed on the first load.
+    loaded: bool,
+}
+
+impl LiveConfig {
+    pub fn new(path: PathBuf) -> LiveConfig {
+        LiveConfig {
+            path,
+            current: Config::default(),
+            last_error: None,
+            last_issues: Vec::new(),
+            loaded: false,
+        }
+    }
+
+    /// Reloads the config file if it exists. If it fails to load, the last config that loaded
+    /// successfully is kept, and the error is returned if it's different from the last one.
+    pub fn reload(&mut self) -> Option<ConfigError> {
+        if !self.path.exists() {
+            self.apply(Config::default());
+            self.last_error = None;
+            return None;
+        }
+
+        match Config::from_file(&self.path) {
+            Ok(config) => {
+                if self.last_error.take().is_some() {
+                    println!("{} loaded successfully", self.path.display());
+                }
+
+                self.apply(config);
+                self.report_issues();
+                None
+            }
+            Err(err) => {
+                // The error's cause has the line and column, so it has to be compared too
+                let msg = format!("{:?}", err);
+
+                if self.last_error.as_ref() == Some(&msg) {
+                    return None;
+                }
+
+                self.last_error = Some(msg);
+                Some(err)
+            }
+        }
+    }
+
+    /// Replaces the current config and prints every setting that changed.
+    fn apply(&mut self, config: Config) {
+        if self.loaded {
+            let mut changes = Vec::new();
+
+            if let (Ok(old), Ok(new)) = (self.current.to_json(), config.to_json()) {
+                changed_settings(&old, &new, None, &mut changes);
+            }
+
+            if !changes.is_empty() {
+                println!("{} reloaded:", self.path.display());
+
+                for change in &changes {
+                    println!("  {}", change);
+                }
+            }
+        }
+
+        self.current = config;
+        self.loaded = true;
+    }
+
+    /// Prints problems in the config file that don't stop it from loading, like unknown keys.
+    fn report_issues(&mut self) {
+        let issues = match Config::validate_file(&self.path) {
+            Ok(issues) => issues
+                .iter()
+                .map(|issue| issue.with_path(&self.path))
+                .collect::<Vec<_>>(),
+            Err(_) => return,
+        };
+
+        if issues != self.last_issues {
+            for issue in &issues {
+                eprintln!("{}", issue);
+            }
+
+            self.last_issues = issues;
+        }
+    }
+}
+
+/// Settings whose values are never written to the terminal when they change.
+const SECRET_SETTINGS: &[&str] = &["Email > Password", "MQTT > Password"];
+
+/// Describes every setting that is different between two configs, using the names from the config file.
+/// Lists are treated as a single setting, and the values of secret settings are left out.
+fn changed_settings(
+    old: &serde_json::Value,
+    new: &serde_json::Value,
+    path: Option<&str>,
+    changes: &mut Vec<String>,
+) {
+    use serde_json::Value;
+
+    if old == new {
+        return;
+    }
+
+    let name = path.unwrap_or("config");
+
+    match (old, new) {
+        (Value::Object(old_fields), Value::Object(new_fields)) => {
+            for (key, new_value) in new_fields {
+                let path = match path {
+                    Some(path) => format!("{} > {}", path, key),
+                    None => key.clone(),
+                };
+
+                let old_value = old_fields.get(key).unwrap_or(&Value::Null);
+                changed_settings(old_value, new_value, Some(&path), changes);
+            }
+        }
+        (Value::Array(_), _)
+        | (_, Value::Array(_))
+        | (Value::Object(_), _)
+        | (_, Value::Object(_)) => changes.push(format!("{} changed", name)),
+        _ if SECRET_SETTINGS.contains(&name) => changes.push(format!("{} changed", name)),
+        _ => changes.push(format!("{} changed from {} to {}", name, old, new)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::FixedOffset;
+    use feed::test_feed as feed;
+
+    fn parse(contents: &str) -> Config {
+        Config::parse(contents, Format::Yaml, "test").unwrap().0
+    }
+
+    #[test]
+    fn blacklist_excludes_feeds_matching_any_entry() {
+        let config = parse(
+            "Blacklist:
+               - ID: 1
+               - County: Sacramento
+               - Name: Old Feed
+                 Until: 2000-01-01 00:00",
+        );
+
+        assert!(!config.can_notify(&feed(1, "Feed 1", "Placer")));
+        assert!(!config.can_notify(&feed(2, "Feed 2", "Sacramento")));
+        assert!(config.can_notify(&feed(3, "Feed 3", "Placer")));
+        assert!(config.can_notify(&feed(4, "Old Feed", "Placer")));
+    }
+
+    #[test]
+    fn unlisted_feeds_only_use_settings_matching_their_id() {
+        let config = parse(
+            "Feed Settings:
+               - ID: 1
+                 Spike Percentages: {Minimum Jump: 5}
+               - ID: 2
+                 Spike Percentages: {Minimum Jump: 10}
+               - County: Sacramento
+                 Spike Percentages: {Jump Required: 0.5}
+               - Any: [{ID: 1}, {County: Placer}]
+                 Spike Percentages: {Low Listener Cutoff: 20}
+               - All: [{ID: 2}, {County: Placer}]
+                 Spike Percentages: {Low Listener Cutoff: 30}",
+        );
+
+        let (layers, unknown) = config.get_id_spike_layers(1);
+        let spike = config.spike_from_layers(&layers);
+
+        assert_eq!(spike.minimum_jump, 5.0);
+        assert_eq!(spike.low_listener_cutoff, 20.0);
+        assert_eq!(spike.jump, Spike::default().jump);
+        assert_eq!(unknown, ["Feed Settings #3 (priority 2)"]);
+    }
+
+    #[test]
+    fn hour_ranges_wrap_around_midnight() {
+        let range = HourRange {
+            days: vec![Day::Friday],
+            start_hour: 22,
+            end_hour: 6,
+        };
+
+        // July 6th, 2018 was a Friday
+        let utc = |day, hour| Utc.ymd(2018, 7, day).and_hms(hour, 0, 0);
+
+        assert!(range.contains(&utc(6, 22)));
+        assert!(range.contains(&utc(6, 23)));
+        assert!(range.contains(&utc(7, 0)));
+        assert!(range.contains(&utc(7, 5)));
+        assert!(!range.contains(&utc(7, 6)));
+        assert!(!range.contains(&utc(6, 21)));
+        assert!(!range.contains(&utc(6, 3)));
+        assert!(!range.contains(&utc(7, 23)));
+
+        // The hour is taken from the time's own offset rather than UTC
+        let pacific = FixedOffset::west(7 * 3600).ymd(2018, 7, 6);
+
+        assert!(!range.contains(&pacific.and_hms(21, 0, 0)));
+        assert!(range.contains(&pacific.and_hms(22, 0, 0)));
+    }
+
+    #[test]
+    fn yearly_date_overrides_wrap_around_new_year() {
+        let entry = |date: &str, end_date: &str| DateOverride {
+            description: None,
+            date: ConfigDate::parse(date.to_string()).unwrap(),
+            end_date: Some(ConfigDate::parse(end_date.to_string()).unwrap()),
+            spike: SpikeOverride::default(),
+            suppress_notifications: false,
+            skip_learning: false,
+        };
+
+        let date = |year, month, day| NaiveDate::from_ymd(year, month, day);
+
+        let yearly = entry("12-31", "01-01");
+
+        assert!(yearly.contains(date(2018, 12, 31)));
+        assert!(yearly.contains(date(2019, 1, 1)));
+        assert!(yearly.contains(date(2025, 12, 31)));
+        assert!(!yearly.contains(date(2018, 12, 30)));
+        assert!(!yearly.contains(date(2019, 1, 2)));
+        assert!(!yearly.contains(date(2019, 7, 4)));
+
+        // Fixed dates only apply in their own year
+        let fixed = entry("2018-12-31", "2019-01-01");
+
+        assert!(fixed.contains(date(2018, 12, 31)));
+        assert!(fixed.contains(date(2019, 1, 1)));
+        assert!(!fixed.contains(date(2019, 12, 31)));
+    }
+
+    #[test]
+    fn date_overrides_cant_mix_yearly_and_fixed_dates() {
+        let (config, issues) = issues(
+            concat!(
+                "Date Overrides:\n",
+                "  - Date: 12-24\n",
+                "    End Date: 2018-12-26\n",
+                "  - Date: 2018-12-31\n",
+                "    End Date: 2019-01-01\n",
+            ),
+            Format::Yaml,
+        );
+
+        assert_eq!(config.date_overrides.len(), 1);
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].0, Some((2, 5)));
+        assert!(issues[0].1.contains(
+            "\"Date\" (12-24) and \"End Date\" (2018-12-26) both need a year, or both need to leave it out"
+        ));
+    }
+
+    #[test]
+    fn values_that_cant_be_read_use_the_default() {
+        let (config, issues) = Config::parse(
+            concat!(
+                "Spike Percentage:\n",
+                "  Jump Required: high\n",
+                "  Minimum Jump: 3\n",
+                "Blacklist: [{ID: 1}, {ID: two}, {ID: 3}]\n",
+            ),
+            Format::Yaml,
+            "test",
+        )
+        .unwrap();
+
+        assert_eq!(config.global_spike.jump, Spike::default().jump);
+        assert_eq!(config.global_spike.minimum_jump, 3.0);
+        assert_eq!(config.blacklist.len(), 2);
+
+        let locations = issues
+            .iter()
+            .map(|issue| issue.location)
+            .collect::<Vec<_>>();
+        assert_eq!(locations, [Some((2, 3)), Some((4, 23))]);
+    }
+
+    type Found = Vec<(Option<(usize, usize)>, String)>;
+
+    /// Gets the position and message of every issue.
+    fn issues(contents: &str, format: Format) -> (Config, Found) {
+        let (config, issues) = Config::parse(contents, format, "test").unwrap();
+
+        let issues = issues
+            .into_iter()
+            .map(|issue| (issue.location, issue.message))
+            .collect();
+
+        (config, issues)
+    }
+
+    #[test]
+    fn invalid_enum_values_are_reported_once() {
+        let (config, issues) = issues(
+            "Misc: {Display Mode: Bogus, Maximum Feeds To Display: 3}",
+            Format::Yaml,
+        );
+
+        assert_eq!(config.misc.display_mode, DisplayMode::Individual);
+        assert_eq!(config.misc.max_feeds, 3);
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].0, Some((1, 8)));
+        assert!(issues[0]
+            .1
+            .starts_with("\"Display Mode\" in \"Misc\" will be ignored"));
+    }
+
+    #[test]
+    fn old_key_names_are_still_read() {
+        let (config, issues) = issues(
+            concat!(
+                "Unskewed Average:\n",
+                "  Adjust To Average Percentage: 0.01\n",
+                "  Spikes Required: lots\n",
+            ),
+            Format::Yaml,
+        );
+
+        assert_eq!(config.unskewed_avg.adjust_pcnt, 0.01);
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].0, Some((3, 3)));
+    }
+
+    #[test]
+    fn groups_that_cant_be_read_are_ignored() {
+        let (config, issues) = issues(
+            concat!(
+                "Email:\n",
+                "  Host: localhost\n",
+                "  Port: lots\n",
+                "Misc: 5\n",
+            ),
+            Format::Yaml,
+        );
+
+        assert!(config.email.is_none());
+        assert_eq!(config.misc.max_feeds, Misc::default().max_feeds);
+
+        let locations = issues.iter().map(|issue| issue.0).collect::<Vec<_>>();
+        assert_eq!(locations, [Some((1, 1)), Some((4, 1))]);
+    }
+
+    #[test]
+    fn toml_issues_have_positions() {
+        let (config, issues) = issues(
+            "[Misc]
+             \"Update Time\" = 60
+             \"Display Mode\" = \"Bogus\"
+
+             [[\"Feed Settings\"]]
+             ID = 1
+             Unknown = 1
+
+             [[\"Feed Settings\"]]
+             ID = 2
+             Priority = \"high\"
+
+             [\"Spike Percentage\"]
+             \"Low Listener Increase\" = -1.0",
+            Format::Toml,
+        );
+
+        assert_eq!(config.misc.update_time, 60.0);
+        assert_eq!(config.feed_settings.len(), 1);
+
+        let locations = issues.iter().map(|issue| issue.0).collect::<Vec<_>>();
+        assert_eq!(
+            locations,
+            [Some((3, 14)), Some((7, 14)), Some((9, 14)), Some((14, 14))]
+        );
+    }
+
+    #[test]
+    fn json_issues_have_positions() {
+        let (config, issues) = issues(
+            concat!(
+                "{\n",
+                "  \"Misc\": {\"Update Time\": \"soon\"},\n",
+                "  \"Blacklist\": [{\"ID\": 1}, {\"Name\": 2}],\n",
+                "  \"Whitelist\": [{\"Nmae\": \"Feed\"}]\n",
+                "}\n",
+            ),
+            Format::Json,
+        );
+
+        assert_eq!(config.misc.update_time, Misc::default().update_time);
+        assert_eq!(config.blacklist.len(), 1);
+
+        let locations = issues.iter().map(|issue| issue.0).collect::<Vec<_>>();
+        assert_eq!(locations, [Some((2, 12)), Some((3, 29)), Some((4, 18))]);
+    }
+
+    #[test]
+    fn files_that_cant_be_read_are_errors() {
+        let parse = |contents, format| Config::parse(contents, format, "test").map(|_| ());
+
+        match parse("Misc: [", Format::Yaml) {
+            Err(ConfigError::Yaml(_, _)) => (),
+            other => panic!("expected a YAML error, got {:?}", other),
+        }
+
+        match parse("Misc = [", Format::Toml) {
+            Err(ConfigError::Toml(_, _)) => (),
+            other => panic!("expected a TOML error, got {:?}", other),
+        }
+
+        match parse("{\"Misc\": ", Format::Json) {
+            Err(ConfigError::Json(_, _)) => (),
+            other => panic!("expected a JSON error, got {:?}", other),
+        }
+
+        match parse("- 1\n- 2", Format::Yaml) {
+            Err(ConfigError::Invalid(_, _)) => (),
+            other => panic!("expected an error for the list, got {:?}", other),
+        }
+
+        assert!(parse("# Nothing but comments", Format::Yaml).is_ok());
+    }
+
+    #[test]
+    fn changed_passwords_are_not_printed() {
+        let old = parse("MQTT: {Host: localhost, Password: old}")
+            .to_json()
+            .unwrap();
+        let new = parse("MQTT: {Host: localhost, Password: new, Port: 1884}")
+            .to_json()
+            .unwrap();
+
+        let mut changes = Vec::new();
+        changed_settings(&old, &new, None, &mut changes);
+
+        assert_eq!(
+            changes,
+            [
+                "MQTT > Port changed from 1883 to 1884",
+                "MQTT > Password changed"
+            ]
+        );
+    }
+
+    /// Loads a whitelist with a single entry, so only the feeds it matches can notify.
+    fn whitelist(ident: &str) -> Config {
+        parse(&format!("Whitelist: [{}]", ident))
+    }
+
+    #[test]
+    fn globs_match_the_whole_name_ignoring_case() {
+        let config = whitelist("{Name Matches: '*fire*'}");
+
+        assert!(config.can_notify(&feed(1, "Sacramento FIRE Dispatch", "Sacramento")));
+        assert!(config.can_notify(&feed(2, "fire", "Sacramento")));
+        assert!(!config.can_notify(&feed(3, "Sheriff", "Sacramento")));
+
+        let config = whitelist("{County Matches: 'sac?amento'}");
+
+        assert!(config.can_notify(&feed(1, "Sheriff", "Sacramento")));
+        assert!(!config.can_notify(&feed(2, "Sheriff", "West Sacramento")));
+    }
+
+    #[test]
+    fn regexes_match_anywhere_unless_anchored() {
+        let config = whitelist("{Name Regex: 'Fire'}");
+
+        assert!(config.can_notify(&feed(1, "Sacramento Fire", "Sacramento")));
+        assert!(!config.can_notify(&feed(2, "Sacramento fire", "Sacramento")));
+
+        let config = whitelist("{Name Regex: '^Fire'}");
+
+        assert!(config.can_notify(&feed(1, "Fire Dispatch", "Sacramento")));
+        assert!(!config.can_notify(&feed(2, "Sacramento Fire", "Sacramento")));
+    }
+
+    #[test]
+    fn invalid_regexes_are_reported() {
+        let (config, issues) = issues(
+            "Whitelist:\n  - Name Regex: '(fire'\n  - ID: 1\n",
+            Format::Yaml,
+        );
+
+        assert_eq!(config.whitelist.len(), 1);
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].0, Some((2, 5)));
+        assert!(issues[0]
+            .1
+            .starts_with("an item in \"Whitelist\" will be ignored: regex parse error"));
+    }
+
+    #[test]
+    fn listener_ranges_include_both_ends() {
+        let with_listeners = |listeners| Feed {
+            listeners,
+            ..feed(1, "Feed", "Sacramento")
+        };
+
+        let config = whitelist("{Listeners: {Min: 50, Max: 100}}");
+
+        assert!(!config.can_notify(&with_listeners(49)));
+        assert!(config.can_notify(&with_listeners(50)));
+        assert!(config.can_notify(&with_listeners(100)));
+        assert!(!config.can_notify(&with_listeners(101)));
+
+        let config = whitelist("{Listeners: {Min: 200}}");
+
+        assert!(!config.can_notify(&with_listeners(199)));
+        assert!(config.can_notify(&with_listeners(5000)));
+    }
+
+    #[test]
+    fn idents_can_be_combined() {
+        let config = whitelist(
+            "{All: [{County: Sacramento}, {Not: {Any: [{ID: 1}, {Name Matches: '*police*'}]}}]}",
+        );
+
+        assert!(config.can_notify(&feed(2, "Fire", "Sacramento")));
+        assert!(!config.can_notify(&feed(1, "Fire", "Sacramento")));
+        assert!(!config.can_notify(&feed(3, "Police", "Sacramento")));
+        assert!(!config.can_notify(&feed(4, "Fire", "Placer")));
+    }
+}
@@ -0,0 +1,73 @@
+use regex::{self, Regex};
+use serde::de::{self, Deserialize, Deserializer};
+use serde::{Serialize, Serializer};
+use std::fmt;
+
+/// A pattern from the config file that text can be matched against. It keeps the text it was
+/// written as, so it can be written back out the same way.
+pub struct Pattern {
+    source: String,
+    regex: Regex,
+}
+
+impl Pattern {
+    /// Creates a pattern from a regular expression, which has to match part of the text.
+    pub fn regex(source: String) -> Result<Pattern, regex::Error> {
+        let regex = Regex::new(&source)?;
+        Ok(Pattern { source, regex })
+    }
+
+    /// Creates a pattern from a glob, which has to match all of the text. `*` matches any number of
+    /// characters, `?` matches one character, and case is ignored.
+    pub fn glob(source: String) -> Result<Pattern, regex::Error> {
+        let mut expr = String::from("(?i)^");
+
+        for ch in source.chars() {
+            match ch {
+                '*' => expr.push_str(".*"),
+                '?' => expr.push('.'),
+                _ => expr.push_str(&regex::escape(&ch.to_string())),
+            }
+        }
+
+        expr.push('$');
+
+        let regex = Regex::new(&expr)?;
+        Ok(Pattern { source, regex })
+    }
+
+    pub fn is_match(&self, text: &str) -> bool {
+        self.regex.is_match(text)
+    }
+
+    pub fn deserialize_regex<'de, D>(deserializer: D) -> Result<Pattern, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let source = String::deserialize(deserializer)?;
+        Pattern::regex(source).map_err(de::Error::custom)
+    }
+
+    pub fn deserialize_glob<'de, D>(deserializer: D) -> Result<Pattern, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let source = String::deserialize(deserializer)?;
+        Pattern::glob(source).map_err(de::Error::custom)
+    }
+}
+
+impl fmt::Debug for Pattern {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?}", self.source)
+    }
+}
+
+impl Serialize for Pattern {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(&self.source)
+    }
+}
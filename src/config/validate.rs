@@ -0,0 +1,201 @@
+use super::load::{self, Key};
+use super::Config;
+use serde_json::{self, Value};
+use std::fmt;
+use std::path::Path;
+
+type Location = Option<(usize, usize)>;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Level {
+    Warning,
+    Error,
+}
+
+/// A problem found in a config file.
+#[derive(Debug)]
+pub struct Issue {
+    pub level: Level,
+    /// The line and column of the problem, both starting at 1. They aren't known for every JSON file.
+    pub location: Option<(usize, usize)>,
+    pub message: String,
+}
+
+impl Issue {
+    pub fn new<S: Into<String>>(
+        level: Level,
+        location: Option<(usize, usize)>,
+        message: S,
+    ) -> Issue {
+        Issue {
+            level,
+            location,
+            message: message.into(),
+        }
+    }
+
+    /// Formats the issue with the path of the file it was found in.
+    pub fn with_path(&self, path: &Path) -> String {
+        match self.location {
+            Some(_) => format!("{}:{}", path.display(), self),
+            None => format!("{}: {}", path.display(), self),
+        }
+    }
+}
+
+impl fmt::Display for Issue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let level = match self.level {
+            Level::Warning => "warning",
+            Level::Error => "error",
+        };
+
+        match self.location {
+            Some((line, col)) => write!(f, "{}:{}: {}: {}", line, col, level, self.message),
+            None => write!(f, "{}: {}", level, self.message),
+        }
+    }
+}
+
+/// Compares the values read from the config file to the config that was loaded from them and returns every
+/// problem found. The position of each problem is found with `locate`.
+pub fn validate<F>(raw: &Value, config: &Config, locate: F) -> Vec<Issue>
+where
+    F: Fn(&[Key]) -> Location,
+{
+    let mut issues = Vec::new();
+
+    if let Ok(effective) = serde_json::to_value(config) {
+        compare(raw, &effective, &mut Vec::new(), &locate, &mut issues);
+    }
+
+    issues
+}
+
+/// Reports keys that weren't used and values that were raised or lowered when the config was loaded.
+fn compare<F>(
+    raw: &Value,
+    effective: &Value,
+    path: &mut Vec<Key>,
+    locate: &F,
+    issues: &mut Vec<Issue>,
+) where
+    F: Fn(&[Key]) -> Location,
+{
+    match (raw, effective) {
+        (Value::Object(entries), Value::Object(fields)) => {
+            let names = fields.keys().map(|name| name.as_str()).collect::<Vec<_>>();
+
+            for (key, raw) in entries {
+                let parent = name(path).to_string();
+                path.push(Key::Name(key.clone()));
+
+                match fields.get(load::canonical(key)) {
+                    Some(effective) => compare(raw, effective, path, locate, issues),
+                    None => issues.push(unknown(locate(path), key, &parent, &names)),
+                }
+
+                path.pop();
+            }
+        }
+        (Value::Array(items), Value::Array(values)) => {
+            // Items that couldn't be read were replaced with null
+            let items = items
+                .iter()
+                .enumerate()
+                .filter(|&(_, item)| !item.is_null());
+
+            for ((i, raw), effective) in items.zip(values) {
+                path.push(Key::Index(i));
+                compare(raw, effective, path, locate, issues);
+                path.pop();
+            }
+        }
+        (Value::Number(raw), Value::Number(num)) => {
+            let (raw, num) = match (raw.as_f64(), num.as_f64()) {
+                (Some(raw), Some(num)) => (raw, num),
+                _ => return,
+            };
+
+            // Floats are stored as f32, so they won't match exactly
+            if raw as f32 == num as f32 {
+                return;
+            }
+
+            let (reason, action) = if raw < num {
+                ("below the minimum", "raised")
+            } else {
+                ("above the maximum", "lowered")
+            };
+
+            issues.push(Issue::new(
+                Level::Warning,
+                locate(path),
+                format!(
+                    "\"{}\" is {}, which is {} and will be {} to {}",
+                    name(path),
+                    raw,
+                    reason,
+                    action,
+                    num as f32
+                ),
+            ));
+        }
+        _ => (),
+    }
+}
+
+/// Gets the name of the closest setting in the path. Items in a list are named after the list.
+fn name(path: &[Key]) -> &str {
+    path.iter()
+        .rev()
+        .filter_map(|key| match *key {
+            Key::Name(ref name) => Some(name.as_str()),
+            Key::Index(_) => None,
+        })
+        .next()
+        .unwrap_or("config")
+}
+
+fn unknown(location: Location, key: &str, parent: &str, names: &[&str]) -> Issue {
+    let message = match suggest(key, names) {
+        Some(suggestion) => format!(
+            "unknown key \"{}\" in \"{}\", did you mean \"{}\"?",
+            key, parent, suggestion
+        ),
+        None => format!("unknown key \"{}\" in \"{}\"", key, parent),
+    };
+
+    Issue::new(Level::Error, location, message)
+}
+
+/// Finds the name that is closest to the specified one, if any are close enough to be a likely typo.
+fn suggest<'a>(name: &str, names: &[&'a str]) -> Option<&'a str> {
+    let name = name.to_lowercase();
+
+    names
+        .iter()
+        .map(|&candidate| (distance(&name, &candidate.to_lowercase()), candidate))
+        .filter(|&(dist, candidate)| dist <= (candidate.len() / 3).max(2))
+        .min_by_key(|&(dist, _)| dist)
+        .map(|(_, candidate)| candidate)
+}
+
+/// Calculates the Levenshtein distance between two strings.
+fn distance(a: &str, b: &str) -> usize {
+    let b = b.chars().collect::<Vec<_>>();
+    let mut prev = (0..=b.len()).collect::<Vec<_>>();
+
+    for (i, ca) in a.chars().enumerate() {
+        let mut cur = vec![i + 1];
+
+        for (j, &cb) in b.iter().enumerate() {
+            let cost = if ca == cb { 0 } else { 1 };
+            cur.push((prev[j] + cost).min(prev[j + 1] + 1).min(cur[j] + 1));
+        }
+
+        prev = cur;
+    }
+
+    prev[b.len()]
+}
@@ -1,5 +1,6 @@
+use config::Config;
 use failure;
-use notify;
+use notify::{self, Notifier, NotifyError, Status};
 
 #[derive(Fail, Debug)]
 pub enum Error {
@@ -17,6 +18,12 @@ pub enum Error {
 
     #[fail(display = "statistics error")]
     Statistics(#[cause] ::statistics::StatisticsError),
+
+    #[fail(display = "muted feeds error")]
+    Mute(#[cause] ::mute::MuteError),
+
+    #[fail(display = "notification outbox error")]
+    Outbox(#[cause] ::notify::OutboxError),
 }
 
 fn build_err_msg(err: &failure::Error) -> String {
@@ -34,19 +41,175 @@ fn print_with_backtrace(msg: &str, err: &failure::Error) {
     eprintln!("{}", err.backtrace());
 }
 
-/// Displays the provided error with a notification and by writing it to the terminal
-pub fn display(err: &failure::Error) {
+/// Writes the provided error to the terminal without displaying a notification
+pub fn print(err: &failure::Error) {
+    print_with_backtrace(&build_err_msg(err), err);
+}
+
+/// Displays the provided error with a desktop notification and by writing it to the terminal.
+/// Used when the other notification backends aren't available.
+pub fn display(err: &failure::Error, config: &Config) {
+    let msg = build_err_msg(err);
+    print_with_backtrace(&msg, err);
+
+    let status = Status {
+        error: Some(&msg),
+        failures: 1,
+    };
+
+    if let Err(notif_err) = notify::create_status(&status, config) {
+        print_notify_errs(vec![notif_err]);
+    }
+}
+
+/// Displays the provided error through every notification backend and by writing it to the terminal
+pub fn report(err: &failure::Error, notifier: &mut Notifier, config: &Config) {
     let msg = build_err_msg(err);
     print_with_backtrace(&msg, err);
 
-    match notify::create_error(&msg) {
-        Ok(_) => (),
-        Err(notif_err) => {
-            eprintln!("failed to create error notification:");
-            
-            let notif_err = notif_err.into();
-            let notif_msg = build_err_msg(&notif_err);
-            print_with_backtrace(&notif_msg, &notif_err);
+    let status = Status {
+        error: Some(&msg),
+        failures: 1,
+    };
+
+    print_notify_errs(notifier.show_status(&status, config));
+}
+
+fn print_notify_errs(errs: Vec<NotifyError>) {
+    for notif_err in errs {
+        eprintln!("failed to send status notification:");
+
+        let notif_err = notif_err.into();
+        let notif_msg = build_err_msg(&notif_err);
+        print_with_backtrace(&notif_msg, &notif_err);
+    }
+}
+
+/// Keeps track of consecutive update failures so an outage only displays a notification
+/// when it starts, periodically while it continues, and when it ends.
+pub struct Failures {
+    consecutive: u32,
+    /// The number of failures since the last error notification was displayed.
+    since_notified: u32,
+}
+
+impl Failures {
+    pub fn new() -> Failures {
+        Failures {
+            consecutive: 0,
+            since_notified: 0,
+        }
+    }
+
+    /// Writes the provided error to the terminal, and displays it with a notification if it's
+    /// the first failure in a row or enough failures have happened since the last notification.
+    pub fn failed(&mut self, err: &failure::Error, notifier: &mut Notifier, config: &Config) {
+        let msg = build_err_msg(err);
+        print_with_backtrace(&msg, err);
+
+        if let Some(msg) = self.error_message(&msg, config) {
+            let status = Status {
+                error: Some(&msg),
+                failures: self.consecutive,
+            };
+
+            print_notify_errs(notifier.show_status(&status, config));
         }
     }
+
+    /// Counts a failure, and returns the message of the error notification if one should be shown for it.
+    fn error_message(&mut self, msg: &str, config: &Config) -> Option<String> {
+        self.consecutive += 1;
+        self.since_notified += 1;
+
+        if self.consecutive > 1 && self.since_notified < config.errors.reminder_after {
+            return None;
+        }
+
+        self.since_notified = 0;
+
+        if self.consecutive > 1 {
+            Some(format!(
+                "updates have failed {} times in a row\n{}",
+                self.consecutive, msg
+            ))
+        } else {
+            Some(msg.to_string())
+        }
+    }
+
+    /// Displays a notification if previous updates have failed.
+    pub fn succeeded(&mut self, notifier: &mut Notifier, config: &Config) {
+        if let Some(failures) = self.recovered(config) {
+            let status = Status {
+                error: None,
+                failures,
+            };
+
+            print_notify_errs(notifier.show_status(&status, config));
+        }
+    }
+
+    /// Clears the failures, and returns how many there were if a recovery notification should be shown.
+    fn recovered(&mut self, config: &Config) -> Option<u32> {
+        let failures = self.consecutive;
+
+        self.consecutive = 0;
+        self.since_notified = 0;
+
+        if failures > 0 && config.errors.notify_recovered {
+            Some(failures)
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use config::ErrorNotifications;
+
+    fn with_errors(reminder_after: u32, notify_recovered: bool) -> Config {
+        Config {
+            errors: ErrorNotifications {
+                reminder_after,
+                notify_recovered,
+            },
+            ..Config::default()
+        }
+    }
+
+    #[test]
+    fn reminders_are_shown_after_enough_failures() {
+        let config = with_errors(3, true);
+        let mut failures = Failures::new();
+
+        assert_eq!(failures.error_message("down", &config), Some("down".into()));
+        assert_eq!(failures.error_message("down", &config), None);
+        assert_eq!(failures.error_message("down", &config), None);
+        assert_eq!(
+            failures.error_message("down", &config),
+            Some("updates have failed 4 times in a row\ndown".into())
+        );
+        assert_eq!(failures.error_message("down", &config), None);
+    }
+
+    #[test]
+    fn recovery_is_shown_once_after_failures() {
+        let config = with_errors(3, true);
+        let mut failures = Failures::new();
+
+        assert_eq!(failures.recovered(&config), None);
+
+        failures.error_message("down", &config);
+        failures.error_message("down", &config);
+
+        assert_eq!(failures.recovered(&config), Some(2));
+        assert_eq!(failures.recovered(&config), None);
+
+        // A failure after recovering starts a new outage, so it's shown right away
+        assert_eq!(failures.error_message("down", &config), Some("down".into()));
+        assert_eq!(failures.recovered(&with_errors(3, false)), None);
+    }
 }
@@ -95,30 +95,21 @@ pub fn scrape_all(config: &Config) -> Result<Vec<Feed>, FeedError> {
         feeds.extend(state_feeds);
     }
 
-    filter_whitelist_blacklist(config, &mut feeds);
-
     feeds.sort_by_key(|feed| feed.id);
     feeds.dedup();
 
     Ok(feeds)
 }
 
-fn filter_whitelist_blacklist(config: &Config, feeds: &mut Vec<Feed>) {
-    if !config.whitelist.is_empty() {
-        feeds.retain(|feed| {
-            config
-                .whitelist
-                .iter()
-                .any(|entry| entry.matches_feed(feed))
-        });
-    }
-
-    if !config.blacklist.is_empty() {
-        feeds.retain(|feed| {
-            config
-                .blacklist
-                .iter()
-                .any(|entry| !entry.matches_feed(feed))
-        });
+/// Creates a feed in California with 100 listeners for tests, which can change whatever else they need to.
+#[cfg(test)]
+pub fn test_feed(id: u32, name: &str, county: &str) -> Feed<'static> {
+    Feed {
+        id,
+        name: name.to_string(),
+        listeners: 100,
+        state: State::new(6, "CA"),
+        county: county.to_string(),
+        alert: None,
     }
 }
@@ -6,12 +6,27 @@ extern crate failure;
 #[macro_use]
 extern crate lazy_static;
 
+#[macro_use]
+extern crate serde_derive;
+
+#[macro_use]
+extern crate serde_json;
+
+extern crate base64;
 extern crate chrono;
 extern crate csv;
+extern crate native_tls;
+extern crate regex;
 extern crate reqwest;
 extern crate select;
+extern crate serde;
+extern crate serde_yaml;
+extern crate toml;
 extern crate yaml_rust;
 
+#[cfg(target_os = "linux")]
+extern crate inotify;
+
 #[cfg(windows)]
 extern crate winrt;
 
@@ -21,26 +36,66 @@ mod util;
 mod config;
 mod error;
 mod feed;
+mod mute;
 mod notify;
+mod paths;
 mod statistics;
+mod watch;
 
-use config::Config;
-use chrono::{Timelike, Utc};
-use error::Error;
+use chrono::{DateTime, Local, Timelike, Utc};
+use config::{Config, Format, Level, LiveConfig, SpikeOverride};
+use error::{Error, Failures};
 use feed::Feed;
+use mute::MuteList;
+use notify::{Notifier, Outbox};
+use paths::Paths;
 use statistics::{AverageData, ListenerStats};
-use std::time::Duration;
-use std::path::PathBuf;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use watch::ConfigWatcher;
+
+const USAGE: &str = "usage: bcnotif [--config path] [--data-dir path] \
+                     [check-config [path] | dump-config [path] [yaml|toml|json] | init-config [path] | \
+                     show-feed <feed id> [path] | mute <feed id> [duration | time] | unmute <feed id> | mutes]";
 
 fn main() {
+    let mut args = std::env::args().skip(1).collect::<Vec<_>>();
+
+    let paths = match get_paths(&mut args) {
+        Ok(paths) => paths,
+        Err(err) => {
+            eprintln!("{}", err);
+            eprintln!("{}", USAGE);
+            std::process::exit(2);
+        }
+    };
+
+    match args.first().map(|arg| arg.as_str()) {
+        Some("check-config") => std::process::exit(check_config(args.get(1), &paths)),
+        Some("dump-config") => std::process::exit(dump_config(args.get(1), args.get(2), &paths)),
+        Some("init-config") => std::process::exit(init_config(args.get(1), &paths)),
+        Some("show-feed") => std::process::exit(show_feed(args.get(1), args.get(2), &paths)),
+        Some("mute") => std::process::exit(mute_feed(args.get(1), args.get(2), &paths)),
+        Some("unmute") => std::process::exit(unmute_feed(args.get(1), &paths)),
+        Some("mutes") => std::process::exit(list_mutes(&paths)),
+        Some(arg) => {
+            eprintln!("unknown command: {}", arg);
+            eprintln!("{}", USAGE);
+            std::process::exit(2);
+        }
+        None => (),
+    }
+
     #[cfg(windows)]
     let rt = winrt::RuntimeContext::init();
 
-    match run() {
+    match run(&paths) {
         Ok(_) => (),
         Err(err) => {
             eprintln!("error during init:");
-            error::display(&err.into());
+            error::display(&err.into(), &Config::default());
         }
     }
 
@@ -48,60 +103,572 @@ fn main() {
     rt.uninit();
 }
 
-fn run() -> Result<(), Error> {
-    let exe_dir = get_exe_directory().map_err(Error::Io)?;
-    let config_path = exe_dir.clone().join("config.yaml");
+/// Finds where the config file and saved data are stored, using the paths specified on the command line first.
+fn get_paths(args: &mut Vec<String>) -> Result<Paths, String> {
+    let config = take_flag(args, "--config")?;
+    let data_dir = take_flag(args, "--data-dir")?;
+
+    Paths::new(config, data_dir)
+        .map_err(|err| format!("failed to find the executable's directory: {}", err))
+}
+
+/// Removes a flag and the path after it from the arguments, and returns the path.
+fn take_flag(args: &mut Vec<String>, name: &str) -> Result<Option<PathBuf>, String> {
+    let pos = match args.iter().position(|arg| arg == name) {
+        Some(pos) => pos,
+        None => return Ok(None),
+    };
+
+    if pos + 1 >= args.len() {
+        return Err(format!("{} requires a path", name));
+    }
+
+    let path = args.remove(pos + 1);
+    args.remove(pos);
+
+    Ok(Some(PathBuf::from(path)))
+}
+
+/// Gets the path of the config file to use for a command, which is the one that is normally loaded by default.
+fn config_path(path: Option<&String>, paths: &Paths) -> PathBuf {
+    match path {
+        Some(path) => PathBuf::from(path),
+        None => paths.config_file(),
+    }
+}
+
+/// Prints every problem in the config file. Returns the exit code, which is 1 if there are any errors.
+fn check_config(path: Option<&String>, paths: &Paths) -> i32 {
+    let path = config_path(path, paths);
+
+    let issues = match Config::validate_file(&path) {
+        Ok(issues) => issues,
+        Err(err) => {
+            error::print(&Error::Config(err).into());
+            return 1;
+        }
+    };
+
+    for issue in &issues {
+        println!("{}", issue.with_path(&path));
+    }
+
+    let errors = issues
+        .iter()
+        .filter(|issue| issue.level == Level::Error)
+        .count();
+    let warnings = issues.len() - errors;
+
+    println!(
+        "{}: {} errors, {} warnings",
+        path.display(),
+        errors,
+        warnings
+    );
+
+    if errors > 0 {
+        1
+    } else {
+        0
+    }
+}
+
+/// Prints the config with every default value filled in, in the format of the file unless another one is specified.
+/// Returns the exit code.
+fn dump_config(path: Option<&String>, format: Option<&String>, paths: &Paths) -> i32 {
+    let path = config_path(path, paths);
+
+    let format = match format {
+        Some(name) => match Format::from_name(name) {
+            Some(format) => format,
+            None => {
+                eprintln!("unknown format: {}", name);
+                eprintln!("expected yaml, toml, or json");
+                return 2;
+            }
+        },
+        None => Format::of(&path),
+    };
+
+    let config = if path.exists() {
+        Config::from_file(&path)
+    } else {
+        Ok(Config::default())
+    };
+
+    match config.and_then(|config| config.dump(format)) {
+        Ok(output) => {
+            println!("{}", output);
+            0
+        }
+        Err(err) => {
+            error::print(&Error::Config(err).into());
+            1
+        }
+    }
+}
+
+/// Writes a config file with every setting, its default value, and its description. Returns the exit code.
+fn init_config(path: Option<&String>, paths: &Paths) -> i32 {
+    let path = config_path(path, paths);
+
+    if path.exists() {
+        eprintln!("{} already exists", path.display());
+        return 1;
+    }
+
+    // The descriptions and examples are written in YAML
+    if Format::of(&path) != Format::Yaml {
+        eprintln!("the config file can only be generated as YAML");
+        return 2;
+    }
+
+    let result = Config::annotated_default()
+        .map_err(Error::Config)
+        .and_then(|contents| write_config(&path, &contents).map_err(Error::Io));
+
+    match result {
+        Ok(_) => {
+            println!("wrote {}", path.display());
+            0
+        }
+        Err(err) => {
+            error::print(&err.into());
+            1
+        }
+    }
+}
+
+/// Prints the spike values that a feed uses and where each of them comes from. Returns the exit code.
+fn show_feed(id: Option<&String>, path: Option<&String>, paths: &Paths) -> i32 {
+    let id = match feed_id_arg(id, "show-feed") {
+        Some(id) => id,
+        None => return 2,
+    };
 
-    let mut averages = AverageData::new(exe_dir.join("averages.csv"));
+    let path = config_path(path, paths);
+
+    let config = if path.exists() {
+        Config::from_file(&path)
+    } else {
+        Ok(Config::default())
+    };
+
+    let config = match config {
+        Ok(config) => config,
+        Err(err) => {
+            error::print(&Error::Config(err).into());
+            return 1;
+        }
+    };
+
+    // Most settings are matched by the data scraped for a feed, so when it isn't listed, only the
+    // settings that match it by ID can be resolved
+    let feeds = match feed::scrape_all(&config) {
+        Ok(feeds) => feeds,
+        Err(err) => {
+            error::print(&Error::Feed(err).into());
+            Vec::new()
+        }
+    };
+
+    let (layers, unknown) = match feeds.iter().find(|feed| feed.id == id) {
+        Some(feed) => {
+            print_feed_status(feed, &config);
+            (config.get_spike_layers(feed), Vec::new())
+        }
+        None => {
+            println!(
+                "[{}] isn't listed, so only the settings that match its ID are used",
+                id
+            );
+
+            config.get_id_spike_layers(id)
+        }
+    };
+
+    let spike = config.spike_from_layers(&layers);
+
+    println!("layers, from lowest to highest priority:");
+    println!("  Spike Percentage");
+
+    for layer in &layers {
+        println!("  {}", layer.source);
+    }
+
+    if !unknown.is_empty() {
+        println!("layers that can't be checked without the feed's data:");
+
+        for source in &unknown {
+            println!("  {}", source);
+        }
+    }
+
+    type Field = (&'static str, f32, fn(&SpikeOverride) -> Option<f32>);
+
+    let fields: [Field; 7] = [
+        ("Jump Required", spike.jump, |layer| layer.jump),
+        (
+            "Low Listener Increase",
+            spike.low_listener_increase,
+            |layer| layer.low_listener_increase,
+        ),
+        ("High Listener Decrease", spike.high_listener_dec, |layer| {
+            layer.high_listener_dec
+        }),
+        (
+            "High Listener Decrease Per Listeners",
+            spike.high_listener_dec_every,
+            |layer| layer.high_listener_dec_every,
+        ),
+        ("Minimum Jump", spike.minimum_jump, |layer| {
+            layer.minimum_jump
+        }),
+        ("Low Listener Cutoff", spike.low_listener_cutoff, |layer| {
+            layer.low_listener_cutoff
+        }),
+        ("Exit Jump Ratio", spike.exit_jump_ratio, |layer| {
+            layer.exit_jump_ratio
+        }),
+    ];
+
+    println!("spike values:");
+
+    for &(name, value, get) in &fields {
+        let source = layers
+            .iter()
+            .rev()
+            .find(|layer| get(layer.spike).is_some())
+            .map(|layer| layer.source.as_str())
+            .unwrap_or("Spike Percentage");
+
+        println!("  {}: {} (from {})", name, value, source);
+    }
+
+    0
+}
+
+/// Prints a listed feed along with the filters that apply to it.
+fn print_feed_status(feed: &Feed, config: &Config) {
+    println!(
+        "[{}] {} ({}, {}) with {} listeners",
+        feed.id, feed.name, feed.county, feed.state.abbrev, feed.listeners
+    );
+
+    let yes_no = |value| if value { "yes" } else { "no" };
+
+    println!("tracked: {}", yes_no(config.is_tracked(feed)));
+    println!("can notify: {}", yes_no(config.can_notify(feed)));
+    println!(
+        "notifications suppressed: {}",
+        yes_no(config.notifications_suppressed(feed))
+    );
+    println!("skips learning: {}", yes_no(config.skips_learning(feed)));
+}
+
+/// Mutes a feed for a duration, until a time, or forever. Returns the exit code.
+fn mute_feed(id: Option<&String>, expiry: Option<&String>, paths: &Paths) -> i32 {
+    let id = match feed_id_arg(id, "mute") {
+        Some(id) => id,
+        None => return 2,
+    };
+
+    let until = match expiry {
+        Some(expiry) => {
+            let until = match util::parse_duration(expiry) {
+                Some(duration) => Utc::now().checked_add_signed(duration),
+                None => util::parse_time(expiry),
+            };
+
+            match until {
+                Some(until) if until <= Utc::now() => {
+                    eprintln!("{} is in the past", format_time(until));
+                    return 2;
+                }
+                Some(until) => Some(until),
+                None => {
+                    eprintln!("invalid duration or time: {}", expiry);
+                    eprintln!("expected a duration like \"30m\", \"2h\", or \"1d\", or a time like \"2018-06-01 18:00\"");
+                    return 2;
+                }
+            }
+        }
+        None => None,
+    };
+
+    let result = load_mutes(paths).and_then(|mut mutes| {
+        mutes.mute_until(id, until);
+        mutes.save().map_err(Error::Mute)
+    });
+
+    match result {
+        Ok(_) => {
+            match until {
+                Some(until) => println!("muted feed {} until {}", id, format_time(until)),
+                None => println!("muted feed {}", id),
+            }
+
+            0
+        }
+        Err(err) => {
+            error::print(&err.into());
+            1
+        }
+    }
+}
+
+/// Removes the mute of a feed. Returns the exit code, which is 1 if the feed wasn't muted.
+fn unmute_feed(id: Option<&String>, paths: &Paths) -> i32 {
+    let id = match feed_id_arg(id, "unmute") {
+        Some(id) => id,
+        None => return 2,
+    };
+
+    let result = load_mutes(paths).and_then(|mut mutes| {
+        if !mutes.unmute(id) {
+            return Ok(false);
+        }
+
+        mutes.save().map_err(Error::Mute)?;
+        Ok(true)
+    });
+
+    match result {
+        Ok(true) => {
+            println!("unmuted feed {}", id);
+            0
+        }
+        Ok(false) => {
+            eprintln!("feed {} isn't muted", id);
+            1
+        }
+        Err(err) => {
+            error::print(&err.into());
+            1
+        }
+    }
+}
+
+/// Prints every muted feed and when its mute expires. Returns the exit code.
+fn list_mutes(paths: &Paths) -> i32 {
+    let mutes = match load_mutes(paths) {
+        Ok(mutes) => mutes,
+        Err(err) => {
+            error::print(&err.into());
+            return 1;
+        }
+    };
+
+    let mut feeds = mutes
+        .feeds
+        .iter()
+        .filter(|&(&id, _)| mutes.is_muted(id))
+        .collect::<Vec<_>>();
+
+    feeds.sort_by_key(|&(&id, _)| id);
+
+    if feeds.is_empty() {
+        println!("no feeds are muted");
+    }
+
+    for (id, until) in feeds {
+        match *until {
+            Some(until) => println!("{} until {}", id, format_time(until)),
+            None => println!("{} forever", id),
+        }
+    }
+
+    0
+}
+
+/// Loads the saved mutes for the mute commands.
+fn load_mutes(paths: &Paths) -> Result<MuteList, Error> {
+    paths.prepare().map_err(Error::Io)?;
+
+    let mut mutes = MuteList::new(paths.data_file("mutes.csv"));
+    mutes.reload().map_err(Error::Mute)?;
+
+    Ok(mutes)
+}
+
+/// Parses the feed ID argument of a command, and prints the usage if it's missing or invalid.
+fn feed_id_arg(arg: Option<&String>, command: &str) -> Option<u32> {
+    match arg.map(|arg| arg.parse()) {
+        Some(Ok(id)) => Some(id),
+        Some(Err(_)) | None => {
+            eprintln!("{} requires a feed ID", command);
+            eprintln!("{}", USAGE);
+            None
+        }
+    }
+}
+
+fn format_time(time: DateTime<Utc>) -> String {
+    time.with_timezone(&Local)
+        .format("%Y-%m-%d %H:%M")
+        .to_string()
+}
+
+fn write_config(path: &Path, contents: &str) -> std::io::Result<()> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+
+    std::fs::write(path, contents)
+}
+
+fn run(paths: &Paths) -> Result<(), Error> {
+    paths.prepare().map_err(Error::Io)?;
+
+    let mut live_config = LiveConfig::new(paths.config_file());
+    let mut averages = AverageData::new(paths.data_file("averages.csv"));
 
     if averages.path.exists() {
         averages.load().map_err(Error::Statistics)?;
     }
 
+    let mut mutes = MuteList::new(paths.data_file("mutes.csv"));
+
+    // Losing the mutes or failed notifications is better than not monitoring feeds at all
+    if mutes.path.exists() {
+        if let Err(err) = mutes.load() {
+            error::print(&Error::Mute(err).into());
+            set_aside(&mutes.path);
+        }
+    }
+
+    // Mutes are added by notification actions, which are listened for on a separate thread
+    let mutes = Arc::new(Mutex::new(mutes));
+    let mut outbox = Outbox::new(paths.data_file("outbox.json"));
+
+    if outbox.path.exists() {
+        if let Err(err) = outbox.load() {
+            error::print(&Error::Outbox(err).into());
+            set_aside(&outbox.path);
+        }
+    }
+
+    let mut notifier = Notifier::new(mutes.clone(), outbox);
+    let mut failures = Failures::new();
+    let mut watcher = ConfigWatcher::new(&live_config.path);
+
     loop {
-        let config = if !config_path.exists() {
-            Config::default()
-        } else {
-            Config::from_file(&config_path).map_err(Error::Config)?
-        };
+        reload_config(&mut live_config, &mut notifier);
+
+        let started = Instant::now();
+        let config = &live_config.current;
+
+        match perform_update(&mut averages, &mutes, &mut notifier, config) {
+            Ok(_) => failures.succeeded(&mut notifier, config),
+            Err(err) => failures.failed(&err.into(), &mut notifier, config),
+        }
+
+        // The config is reloaded as soon as it changes, so a new update time applies to the current wait
+        loop {
+            let update_time =
+                Duration::from_secs((live_config.current.misc.update_time * 60.0) as u64);
+            let elapsed = started.elapsed();
 
-        match perform_update(&mut averages, &config) {
-            Ok(_) => (),
-            Err(err) => error::display(&err.into()),
+            if elapsed >= update_time || !watcher.wait(update_time - elapsed) {
+                break;
+            }
+
+            reload_config(&mut live_config, &mut notifier);
         }
+    }
+}
+
+/// Renames a file that couldn't be read so it isn't overwritten the next time it's saved.
+fn set_aside(path: &Path) {
+    let mut aside = path.as_os_str().to_owned();
+    aside.push(".bad");
+
+    match std::fs::rename(path, &aside) {
+        Ok(_) => eprintln!(
+            "moved {} to {} and started without it",
+            path.display(),
+            Path::new(&aside).display()
+        ),
+        Err(err) => eprintln!("failed to move {} aside: {}", path.display(), err),
+    }
+}
 
-        std::thread::sleep(Duration::from_secs((config.misc.update_time * 60.0) as u64));
+fn reload_config(live_config: &mut LiveConfig, notifier: &mut Notifier) {
+    // A broken config file shouldn't stop monitoring, so the last valid config is used instead
+    if let Some(err) = live_config.reload() {
+        error::report(&Error::Config(err).into(), notifier, &live_config.current);
     }
 }
 
-fn perform_update(averages: &mut AverageData, config: &Config) -> Result<(), Error> {
+fn perform_update(
+    averages: &mut AverageData,
+    mutes: &Mutex<MuteList>,
+    notifier: &mut Notifier,
+    config: &Config,
+) -> Result<(), Error> {
     let hour = Utc::now().hour();
     let mut display_feeds = Vec::new();
 
     let feeds = feed::scrape_all(config).map_err(Error::Feed)?;
 
+    {
+        let mut mutes = mutes.lock().unwrap();
+
+        // The statistics are more important than picking up new mutes, so the previous mutes are
+        // used until the file can be read again
+        if let Err(err) = mutes.reload() {
+            error::print(&Error::Mute(err).into());
+        }
+
+        if mutes.remove_expired() {
+            if let Err(err) = mutes.save() {
+                error::print(&Error::Mute(err).into());
+            }
+        }
+    }
+
     for feed in feeds {
-        if feed.listeners < config.misc.minimum_listeners {
+        if !config.is_tracked(&feed) {
             continue;
         }
 
         let stats = update_feed_stats(hour, &feed, config, averages);
+        notifier.record_stats(&feed, stats, config);
 
         if cfg!(feature = "print-feed-data") {
             print_info(&feed, stats);
         }
 
-        let can_show = stats.has_spiked || feed.alert.is_some();
+        // Feeds that can't notify still need their statistics updated so they're accurate
+        // when they're allowed to again
+        if !config.can_notify(&feed) {
+            continue;
+        }
+
+        // Muted feeds still need their statistics updated so they're accurate when the mute expires
+        if mutes.lock().unwrap().is_muted(feed.id) {
+            continue;
+        }
+
+        if config.notifications_suppressed(&feed) {
+            continue;
+        }
 
-        if can_show && (display_feeds.len() as u32) < config.misc.max_feeds {
+        let incident_ended = stats.incident_ended && config.misc.show_incident_end;
+        let can_show = stats.has_spiked || feed.alert.is_some() || incident_ended;
+
+        // Every feed is ranked before the maximum is applied, so the ones shown are the top ones
+        if can_show {
             display_feeds.push((feed, stats.clone()));
         }
     }
 
-    show_feeds(display_feeds, config)?;
+    // The statistics have already been updated, so they should be saved even if
+    // notifications fail
+    let shown = show_feeds(display_feeds, notifier, config);
 
     averages.save().map_err(Error::Statistics)?;
-    Ok(())
+    shown
 }
 
 fn update_feed_stats<'a>(
@@ -121,20 +688,13 @@ fn update_feed_stats<'a>(
 
 fn sort_feeds(feeds: &mut Vec<(Feed, ListenerStats)>, config: &Config) {
     use config::{SortOrder, SortType};
+    use std::cmp::Ordering;
 
-    feeds.sort_unstable_by(|x, y| {
-        let (x, y) = match config.sorting.sort_order {
-            SortOrder::Ascending => (x, y),
-            SortOrder::Descending => (y, x),
-        };
-
-        let x_feed = &x.0;
-        let x_stats = &x.1;
-
-        let y_feed = &y.0;
-        let y_stats = &y.1;
+    let compare = |sort_type, x: &(Feed, ListenerStats), y: &(Feed, ListenerStats)| {
+        let (x_feed, x_stats) = (&x.0, &x.1);
+        let (y_feed, y_stats) = (&y.0, &y.1);
 
-        match config.sorting.sort_type {
+        match sort_type {
             SortType::Listeners => x_feed.listeners.cmp(&y_feed.listeners),
             SortType::Jump => {
                 let x_jump = x_stats.get_jump(x_feed.listeners) as i32;
@@ -142,20 +702,85 @@ fn sort_feeds(feeds: &mut Vec<(Feed, ListenerStats)>, config: &Config) {
 
                 x_jump.cmp(&y_jump)
             }
+            SortType::JumpPercent => {
+                let x_jump = x_stats.get_jump_pcnt(x_feed.listeners);
+                let y_jump = y_stats.get_jump_pcnt(y_feed.listeners);
+
+                x_jump.partial_cmp(&y_jump).unwrap_or(Ordering::Equal)
+            }
+            SortType::Severity => {
+                let x_severity = config.severity.get(x_feed, x_stats);
+                let y_severity = config.severity.get(y_feed, y_stats);
+
+                x_severity.cmp(&y_severity)
+            }
+            SortType::SpikeCount => x_stats.spike_count.cmp(&y_stats.spike_count),
+            SortType::Alert => x_feed.alert.is_some().cmp(&y_feed.alert.is_some()),
         }
+    };
+
+    feeds.sort_by(|x, y| {
+        let (x, y) = match config.sorting.sort_order {
+            SortOrder::Ascending => (x, y),
+            SortOrder::Descending => (y, x),
+        };
+
+        config
+            .sorting
+            .sort_types
+            .iter()
+            .fold(Ordering::Equal, |order, &sort_type| {
+                order.then_with(|| compare(sort_type, x, y))
+            })
     });
 }
 
-fn show_feeds(mut feeds: Vec<(Feed, ListenerStats)>, config: &Config) -> Result<(), Error> {
+/// Ranks the feeds and keeps the top ones that fit within the maximum number of feeds, and the maximum
+/// for each state and county.
+fn select_feeds<'a>(
+    mut feeds: Vec<(Feed<'a>, ListenerStats)>,
+    config: &Config,
+) -> Vec<(Feed<'a>, ListenerStats)> {
     sort_feeds(&mut feeds, config);
 
-    let total = feeds.len() as i32;
+    let misc = &config.misc;
+    let below = |count: u32, max: Option<u32>| max.map(|max| count < max).unwrap_or(true);
+
+    let mut per_state = HashMap::new();
+    let mut per_county = HashMap::new();
+    let mut selected = Vec::new();
 
-    for (i, (feed, stats)) in feeds.into_iter().enumerate() {
-        notify::create_update(1 + i as i32, total, &feed, &stats).map_err(Error::Notify)?;
+    for (feed, stats) in feeds {
+        if selected.len() as u32 >= misc.max_feeds {
+            break;
+        }
+
+        let state_count = per_state.entry(feed.state.id).or_insert(0);
+        let county_count = per_county
+            .entry((feed.state.id, feed.county.clone()))
+            .or_insert(0);
+
+        if !below(*state_count, misc.max_feeds_per_state)
+            || !below(*county_count, misc.max_feeds_per_county)
+        {
+            continue;
+        }
+
+        *state_count += 1;
+        *county_count += 1;
+        selected.push((feed, stats));
     }
 
-    Ok(())
+    selected
+}
+
+fn show_feeds(
+    feeds: Vec<(Feed, ListenerStats)>,
+    notifier: &mut Notifier,
+    config: &Config,
+) -> Result<(), Error> {
+    let feeds = select_feeds(feeds, config);
+    notifier.show_updates(feeds, config).map_err(Error::Notify)
 }
 
 fn print_info(feed: &Feed, stats: &ListenerStats) {
@@ -172,8 +797,73 @@ fn print_info(feed: &Feed, stats: &ListenerStats) {
     println!("\ttimes spiked | {}", stats.spike_count);
 }
 
-fn get_exe_directory() -> std::io::Result<PathBuf> {
-    let mut path = std::env::current_exe()?;
-    path.pop();
-    Ok(path)
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use config::{Misc, SortOrder, SortType, Sorting};
+    use feed::{test_feed, State};
+
+    fn feed(id: u32, county: &str, listeners: u32) -> (Feed<'static>, ListenerStats) {
+        let feed = Feed {
+            listeners,
+            ..test_feed(id, "Feed", county)
+        };
+
+        (feed, ListenerStats::new())
+    }
+
+    fn ids(feeds: &[(Feed, ListenerStats)]) -> Vec<u32> {
+        feeds.iter().map(|(feed, _)| feed.id).collect()
+    }
+
+    #[test]
+    fn later_sort_types_break_ties() {
+        let config = Config {
+            sorting: Sorting {
+                sort_types: vec![SortType::Alert, SortType::Listeners],
+                sort_order: SortOrder::Descending,
+            },
+            ..Config::default()
+        };
+
+        let mut feeds = vec![
+            feed(1, "Sacramento", 50),
+            feed(2, "Sacramento", 300),
+            feed(3, "Sacramento", 200),
+            feed(4, "Sacramento", 100),
+        ];
+
+        feeds[0].0.alert = Some("Evacuations".into());
+        feeds[2].0.alert = Some("Evacuations".into());
+
+        sort_feeds(&mut feeds, &config);
+        assert_eq!(ids(&feeds), [3, 1, 2, 4]);
+    }
+
+    #[test]
+    fn each_state_and_county_only_gets_some_of_the_feeds() {
+        let config = Config {
+            misc: Misc {
+                max_feeds: 10,
+                max_feeds_per_state: Some(3),
+                max_feeds_per_county: Some(1),
+                ..Misc::default()
+            },
+            ..Config::default()
+        };
+
+        let mut other_state = feed(6, "Clark", 10);
+        other_state.0.state = State::new(32, "NV");
+
+        let feeds = vec![
+            feed(1, "Sacramento", 600),
+            feed(2, "Sacramento", 500),
+            feed(3, "Placer", 400),
+            feed(4, "Yolo", 300),
+            feed(5, "Butte", 200),
+            other_state,
+        ];
+
+        assert_eq!(ids(&select_feeds(feeds, &config)), [1, 3, 4, 6]);
+    }
 }
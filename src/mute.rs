@@ -0,0 +1,207 @@
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use csv;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+#[derive(Fail, Debug)]
+pub enum MuteError {
+    #[fail(display = "CSV error")]
+    CSV(#[cause] ::csv::Error),
+
+    #[fail(display = "{}", _0)]
+    Io(#[cause] ::std::io::Error),
+
+    #[fail(display = "{}", _0)]
+    ParseIntError(#[cause] ::std::num::ParseIntError),
+
+    #[fail(display = "CSV file contains record with too few rows")]
+    TooFewRows,
+
+    #[fail(display = "CSV file contains an invalid expiration time: {}", _0)]
+    InvalidTime(i64),
+}
+
+type FeedID = u32;
+
+/// An interface to save and load feeds that should not be displayed.
+pub struct MuteList {
+    /// The path to the file to save and load mutes from.
+    pub path: PathBuf,
+    /// The muted feeds, along with the time their mute expires.
+    /// Feeds without an expiration time stay muted until they are removed from the file.
+    pub feeds: HashMap<FeedID, Option<DateTime<Utc>>>,
+}
+
+impl MuteList {
+    pub fn new(path: PathBuf) -> MuteList {
+        MuteList {
+            path,
+            feeds: HashMap::new(),
+        }
+    }
+
+    pub fn load(&mut self) -> Result<(), MuteError> {
+        let feeds = self.read()?;
+        self.feeds.extend(feeds);
+        Ok(())
+    }
+
+    fn read(&self) -> Result<HashMap<FeedID, Option<DateTime<Utc>>>, MuteError> {
+        let mut feeds = HashMap::new();
+
+        let mut rdr = csv::ReaderBuilder::new()
+            .has_headers(false)
+            .from_path(&self.path)
+            .map_err(MuteError::CSV)?;
+
+        for result in rdr.records() {
+            let record = result.map_err(MuteError::CSV)?;
+
+            if record.len() < 2 {
+                return Err(MuteError::TooFewRows);
+            }
+
+            let id = record[0].parse().map_err(MuteError::ParseIntError)?;
+
+            // An empty expiration time means that the feed is muted forever
+            let until = match &record[1] {
+                "" => None,
+                time => {
+                    let time = time.parse().map_err(MuteError::ParseIntError)?;
+
+                    let until = Utc
+                        .timestamp_opt(time, 0)
+                        .single()
+                        .ok_or(MuteError::InvalidTime(time))?;
+
+                    Some(until)
+                }
+            };
+
+            feeds.insert(id, until);
+        }
+
+        Ok(feeds)
+    }
+
+    /// Replaces the mutes with the ones in the file, so mutes added by other processes (like the
+    /// mute command) are picked up before the list is used or changed. The mutes are left alone
+    /// if the file can't be read.
+    pub fn reload(&mut self) -> Result<(), MuteError> {
+        self.feeds = if self.path.exists() {
+            self.read()?
+        } else {
+            HashMap::new()
+        };
+
+        Ok(())
+    }
+
+    /// Saves the mutes to a temporary file and then replaces the file with it, so other processes
+    /// never read a partly written file.
+    pub fn save(&self) -> Result<(), MuteError> {
+        let temp_path = self.path.with_extension("csv.tmp");
+
+        self.write(&temp_path)?;
+        fs::rename(&temp_path, &self.path).map_err(MuteError::Io)
+    }
+
+    fn write(&self, path: &Path) -> Result<(), MuteError> {
+        let mut wtr = csv::Writer::from_path(path).map_err(MuteError::CSV)?;
+
+        for (id, until) in &self.feeds {
+            let until = until
+                .map(|until| until.timestamp().to_string())
+                .unwrap_or_default();
+
+            wtr.write_record(&[id.to_string(), until])
+                .map_err(MuteError::CSV)?;
+        }
+
+        wtr.flush().map_err(MuteError::Io)?;
+        Ok(())
+    }
+
+    /// Mutes the specified feed for the given duration, or forever if no duration is specified.
+    pub fn mute(&mut self, id: FeedID, duration: Option<Duration>) {
+        // A duration too long to add to the current time is as good as forever
+        let until = duration.and_then(|duration| Utc::now().checked_add_signed(duration));
+        self.mute_until(id, until);
+    }
+
+    /// Mutes the specified feed until the given time, or forever if no time is specified.
+    pub fn mute_until(&mut self, id: FeedID, until: Option<DateTime<Utc>>) {
+        self.feeds.insert(id, until);
+    }
+
+    /// Removes the mute of the specified feed. Returns true if it was muted.
+    pub fn unmute(&mut self, id: FeedID) -> bool {
+        self.feeds.remove(&id).is_some()
+    }
+
+    pub fn is_muted(&self, id: FeedID) -> bool {
+        match self.feeds.get(&id) {
+            Some(&Some(until)) => Utc::now() < until,
+            Some(&None) => true,
+            None => false,
+        }
+    }
+
+    /// Removes every mute that has expired. Returns true if any were removed.
+    pub fn remove_expired(&mut self) -> bool {
+        let now = Utc::now();
+        let len = self.feeds.len();
+
+        self.feeds.retain(|_, until| match *until {
+            Some(until) => now < until,
+            None => true,
+        });
+
+        self.feeds.len() != len
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::process;
+
+    #[test]
+    fn mutes_are_saved_and_loaded() {
+        let path = env::temp_dir().join(format!("bcnotif-mutes-{}.csv", process::id()));
+        // Expiration times are saved as whole seconds
+        let until = Utc.timestamp(Utc::now().timestamp() + 3600, 0);
+
+        let mut mutes = MuteList::new(path.clone());
+        mutes.mute_until(1, Some(until));
+        mutes.mute_until(2, None);
+        mutes.save().unwrap();
+
+        let mut loaded = MuteList::new(path.clone());
+        loaded.load().unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(loaded.feeds, mutes.feeds);
+    }
+
+    #[test]
+    fn expired_mutes_are_removed() {
+        let mut mutes = MuteList::new(PathBuf::new());
+        mutes.mute(1, Some(Duration::hours(1)));
+        mutes.mute_until(2, Some(Utc::now() - Duration::seconds(1)));
+        mutes.mute(3, None);
+
+        assert!(mutes.is_muted(1));
+        assert!(!mutes.is_muted(2));
+        assert!(mutes.is_muted(3));
+
+        assert!(mutes.remove_expired());
+        assert!(!mutes.remove_expired());
+
+        let mut ids = mutes.feeds.keys().cloned().collect::<Vec<_>>();
+        ids.sort();
+        assert_eq!(ids, [1, 3]);
+    }
+}
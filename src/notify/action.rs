@@ -0,0 +1,113 @@
+use chrono::Duration;
+use config::Config;
+use error;
+use feed::Feed;
+use mute::{MuteError, MuteList};
+use std::io;
+use std::process::Command;
+use std::sync::{Arc, Mutex};
+
+#[derive(Fail, Debug)]
+pub enum ActionError {
+    #[fail(display = "failed to run {}", _1)]
+    Open(#[cause] io::Error, String),
+
+    #[fail(display = "failed to save muted feeds")]
+    Mute(#[cause] MuteError),
+}
+
+/// The actions that can be taken from a feed's notification.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Action {
+    Open,
+    MuteTemporarily,
+    Mute,
+}
+
+impl Action {
+    pub const ALL: [Action; 3] = [Action::Open, Action::MuteTemporarily, Action::Mute];
+
+    pub fn id(&self) -> &'static str {
+        match *self {
+            Action::Open => "open",
+            Action::MuteTemporarily => "mute-temporarily",
+            Action::Mute => "mute",
+        }
+    }
+
+    pub fn from_id(id: &str) -> Option<Action> {
+        Action::ALL.iter().cloned().find(|action| action.id() == id)
+    }
+}
+
+/// Everything needed to respond to the actions of a feed's notification after the
+/// notification has been shown.
+pub struct FeedActions {
+    feed_id: u32,
+    url: String,
+    open_command: String,
+    open_args: Vec<String>,
+    mute_hours: f32,
+    mutes: Arc<Mutex<MuteList>>,
+}
+
+impl FeedActions {
+    pub fn new(feed: &Feed, config: &Config, mutes: Arc<Mutex<MuteList>>) -> FeedActions {
+        let actions = &config.actions;
+
+        FeedActions {
+            feed_id: feed.id,
+            url: format!("http://broadcastify.com/listen/feed/{}", feed.id),
+            open_command: actions.open_command.clone(),
+            open_args: actions.open_args.clone(),
+            mute_hours: actions.mute_hours,
+            mutes,
+        }
+    }
+
+    pub fn label(&self, action: Action) -> String {
+        match action {
+            Action::Open => "Open feed".to_string(),
+            Action::MuteTemporarily => format!("Mute {}h", self.mute_hours),
+            Action::Mute => "Mute feed".to_string(),
+        }
+    }
+
+    /// Performs the action with the specified ID. Unknown IDs, such as the one sent
+    /// when the notification is closed, are ignored.
+    pub fn invoke(self, id: &str) {
+        let result = match Action::from_id(id) {
+            Some(Action::Open) => self.open(),
+            Some(Action::MuteTemporarily) => {
+                let minutes = (self.mute_hours * 60.0) as i64;
+                self.mute(Some(Duration::minutes(minutes)))
+            }
+            Some(Action::Mute) => self.mute(None),
+            None => Ok(()),
+        };
+
+        // There's no config to display a notification with from here,
+        // so the error can only be printed
+        if let Err(err) = result {
+            error::print(&err.into());
+        }
+    }
+
+    fn open(&self) -> Result<(), ActionError> {
+        Command::new(&self.open_command)
+            .args(&self.open_args)
+            .arg(&self.url)
+            .status()
+            .map_err(|e| ActionError::Open(e, self.open_command.clone()))?;
+
+        Ok(())
+    }
+
+    fn mute(&self, duration: Option<Duration>) -> Result<(), ActionError> {
+        let mut mutes = self.mutes.lock().unwrap();
+
+        mutes.reload().map_err(ActionError::Mute)?;
+        mutes.mute(self.feed_id, duration);
+        mutes.save().map_err(ActionError::Mute)
+    }
+}
@@ -0,0 +1,606 @@
+use super::template::{self, Kind, Vars};
+use super::{max_severity, Event, Status};
+use base64;
+use chrono::{DateTime, Local};
+use config::{Backend, Config, Email as EmailConfig, Severity};
+use feed::Feed;
+use native_tls::{self, TlsConnector, TlsStream};
+use statistics::ListenerStats;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::mem;
+use std::net::TcpStream;
+use std::slice;
+use std::time::{Duration, Instant};
+
+#[derive(Fail, Debug)]
+pub enum EmailError {
+    #[fail(display = "{}", _0)]
+    Io(#[cause] io::Error),
+
+    #[fail(display = "TLS error")]
+    Tls(#[cause] native_tls::Error),
+
+    #[fail(display = "TLS handshake failed: {}", _0)]
+    TlsHandshake(String),
+
+    #[fail(display = "unexpected SMTP reply: {} {}", _0, _1)]
+    UnexpectedReply(u32, String),
+}
+
+/// A feed update that is waiting to be included in an email.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Entry {
+    pub time: DateTime<Local>,
+    pub title: String,
+    pub body: String,
+    pub severity: Severity,
+}
+
+impl Entry {
+    fn new(config: &Config, feed: &Feed, stats: &ListenerStats) -> Entry {
+        let kind = Kind::Feed(Event::of(feed, stats));
+        let vars = Vars::for_feed(feed, stats, config);
+        let (title, body) = template::render(config, Backend::Email, kind, &vars);
+
+        Entry {
+            time: Local::now(),
+            title,
+            body,
+            severity: config.severity.get(feed, stats),
+        }
+    }
+
+    pub fn status(config: &Config, status: &Status) -> Entry {
+        let (title, body) = status.render(config, Backend::Email);
+
+        Entry {
+            time: Local::now(),
+            title,
+            body,
+            severity: status.severity(),
+        }
+    }
+}
+
+/// Sends feed updates through SMTP, either immediately or batched into a digest.
+pub struct EmailNotifier {
+    pending: Vec<Entry>,
+    last_sent: Instant,
+}
+
+impl EmailNotifier {
+    pub fn new() -> EmailNotifier {
+        EmailNotifier {
+            pending: Vec::new(),
+            last_sent: Instant::now(),
+        }
+    }
+
+    /// Queues the specified feeds and returns every queued entry if digest mode is disabled,
+    /// or if enough time has passed since the last digest was sent.
+    pub fn queue(
+        &mut self,
+        base_config: &Config,
+        config: &EmailConfig,
+        feeds: &[(Feed, ListenerStats)],
+    ) -> Option<Vec<Entry>> {
+        self.pending.extend(
+            feeds
+                .iter()
+                .map(|(feed, stats)| Entry::new(base_config, feed, stats)),
+        );
+
+        if let Some(minutes) = config.digest_minutes {
+            let interval = Duration::from_secs((minutes * 60.0) as u64);
+
+            if self.last_sent.elapsed() < interval {
+                return None;
+            }
+        }
+
+        if self.pending.is_empty() {
+            return None;
+        }
+
+        self.last_sent = Instant::now();
+        Some(mem::take(&mut self.pending))
+    }
+}
+
+fn build_plain(entries: &[Entry]) -> String {
+    let mut body = String::new();
+
+    for entry in entries {
+        body.push_str(&format!(
+            "[{}] {}\n{}\n\n",
+            entry.time.format("%H:%M"),
+            entry.title,
+            entry.body
+        ));
+    }
+
+    body
+}
+
+fn build_html(entries: &[Entry]) -> String {
+    let mut body = String::from("<html><body>\n");
+
+    for entry in entries {
+        let lines = entry
+            .body
+            .lines()
+            .map(|line| linkify(&escape_html(line)))
+            .collect::<Vec<_>>();
+
+        body.push_str(&format!(
+            "<p><b>[{}] {}</b><br>{}</p>\n",
+            entry.time.format("%H:%M"),
+            escape_html(&entry.title),
+            lines.join("<br>")
+        ));
+    }
+
+    body.push_str("</body></html>");
+    body
+}
+
+/// Turns any HTTP links in the (already escaped) text into hyperlinks.
+fn linkify(text: &str) -> String {
+    text.split(' ')
+        .map(|word| {
+            if word.starts_with("http://") || word.starts_with("https://") {
+                format!("<a href=\"{0}\">{0}</a>", word)
+            } else {
+                word.to_string()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+
+    for ch in text.chars() {
+        match ch {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            _ => escaped.push(ch),
+        }
+    }
+
+    escaped
+}
+
+/// The longest a line of a quoted-printable body can be, not counting the line break.
+const MAX_BODY_LINE: usize = 76;
+
+/// Encodes text as quoted-printable, so it only contains ASCII and its lines are short enough
+/// for any mail server. Long lines are broken with soft line breaks that are removed when the
+/// message is read.
+fn quoted_printable(text: &str) -> String {
+    let mut encoded = String::with_capacity(text.len());
+
+    for line in text.split('\n') {
+        let bytes = line.as_bytes();
+        let mut line_len = 0;
+
+        for (i, &byte) in bytes.iter().enumerate() {
+            // Whitespace at the end of a line can be stripped by mail servers, so it's encoded too
+            let is_last = i + 1 == bytes.len();
+
+            let token = match byte {
+                b' ' | b'\t' if !is_last => (byte as char).to_string(),
+                b'=' => "=3D".to_string(),
+                b'!'..=b'~' => (byte as char).to_string(),
+                _ => format!("={:02X}", byte),
+            };
+
+            // The soft line break's "=" needs room at the end of the line
+            if line_len + token.len() > MAX_BODY_LINE - 1 {
+                encoded.push_str("=\n");
+                line_len = 0;
+            }
+
+            encoded.push_str(&token);
+            line_len += token.len();
+        }
+
+        encoded.push('\n');
+    }
+
+    encoded.pop();
+    encoded
+}
+
+/// The longest a header line should be, as recommended by RFC 5322.
+const MAX_HEADER_LINE: usize = 78;
+
+/// Formats a header, folding it onto more lines between words when it's too long.
+fn header(name: &str, words: &[String]) -> String {
+    let mut header = format!("{}:", name);
+    let mut line_len = header.len();
+
+    for (i, word) in words.iter().enumerate() {
+        if i > 0 && line_len + 1 + word.len() > MAX_HEADER_LINE {
+            header.push('\n');
+            line_len = 0;
+        }
+
+        header.push(' ');
+        header.push_str(word);
+        line_len += 1 + word.len();
+    }
+
+    header
+}
+
+/// Splits a header's text into words. Headers can only contain ASCII, so text with other characters
+/// is encoded as RFC 2047 encoded words.
+fn text_words(text: &str) -> Vec<String> {
+    // Line breaks would end the header early, so they're replaced along with other control characters
+    let text = text.replace(|ch: char| ch.is_control(), " ");
+
+    if text.is_ascii() {
+        text.split(' ').map(String::from).collect()
+    } else {
+        encode_words(&text)
+    }
+}
+
+/// Splits text into base64 encoded words that each fit on a header line.
+fn encode_words(text: &str) -> Vec<String> {
+    // Encoded words can be 75 characters long, but they're kept to 60 so the first one still fits on
+    // the line with the header's name. That leaves room for 36 bytes of text after the "=?UTF-8?B?"
+    // and "?=" around it
+    const MAX_BYTES: usize = 36;
+
+    let mut words = Vec::new();
+    let mut chunk = String::new();
+
+    for ch in text.chars() {
+        if chunk.len() + ch.len_utf8() > MAX_BYTES {
+            words.push(format!("=?UTF-8?B?{}?=", base64::encode(&chunk)));
+            chunk.clear();
+        }
+
+        chunk.push(ch);
+    }
+
+    if !chunk.is_empty() {
+        words.push(format!("=?UTF-8?B?{}?=", base64::encode(&chunk)));
+    }
+
+    words
+}
+
+fn build_message(config: &EmailConfig, subject: &str, entries: &[Entry]) -> String {
+    const BOUNDARY: &str = "bcnotif-alternative-boundary";
+
+    // Mail clients can only show one priority per message, so use the highest one
+    let priority = match entries
+        .iter()
+        .map(|entry| entry.severity)
+        .fold(None, max_severity)
+    {
+        Some(Severity::Critical) => "1 (Highest)",
+        Some(Severity::Major) => "2 (High)",
+        _ => "3 (Normal)",
+    };
+
+    // Folding only adds line breaks before spaces, so the addresses themselves are left alone
+    let recipients = config
+        .to
+        .join(", ")
+        .split(' ')
+        .map(String::from)
+        .collect::<Vec<_>>();
+
+    let headers = format!(
+        "From: {}\n{}\n{}\nDate: {}\nX-Priority: {}\nMIME-Version: 1.0\n\
+         Content-Type: multipart/alternative; boundary=\"{}\"\n\n",
+        config.from,
+        header("To", &recipients),
+        header("Subject", &text_words(subject)),
+        Local::now().to_rfc2822(),
+        priority,
+        BOUNDARY
+    );
+
+    let message = format!(
+        "{}--{b}\nContent-Type: text/plain; charset=utf-8\n{enc}\n\n{}\n\
+         --{b}\nContent-Type: text/html; charset=utf-8\n{enc}\n\n{}\n--{b}--\n",
+        headers,
+        quoted_printable(&build_plain(entries)),
+        quoted_printable(&build_html(entries)),
+        enc = "Content-Transfer-Encoding: quoted-printable",
+        b = BOUNDARY
+    );
+
+    // SMTP requires CRLF line endings, and lines starting with a period
+    // have to be escaped so they aren't mistaken for the end of the message
+    message
+        .lines()
+        .map(|line| {
+            if line.starts_with('.') {
+                format!(".{}\r\n", line)
+            } else {
+                format!("{}\r\n", line)
+            }
+        })
+        .collect()
+}
+
+enum Stream {
+    Plain(TcpStream),
+    Tls(TlsStream<TcpStream>),
+}
+
+impl Read for Stream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match *self {
+            Stream::Plain(ref mut s) => s.read(buf),
+            Stream::Tls(ref mut s) => s.read(buf),
+        }
+    }
+}
+
+impl Write for Stream {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match *self {
+            Stream::Plain(ref mut s) => s.write(buf),
+            Stream::Tls(ref mut s) => s.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match *self {
+            Stream::Plain(ref mut s) => s.flush(),
+            Stream::Tls(ref mut s) => s.flush(),
+        }
+    }
+}
+
+struct Connection {
+    stream: BufReader<Stream>,
+}
+
+impl Connection {
+    fn open(host: &str, port: u16) -> Result<Connection, EmailError> {
+        let tcp = TcpStream::connect((host, port)).map_err(EmailError::Io)?;
+
+        let timeout = Some(Duration::from_secs(30));
+        tcp.set_read_timeout(timeout).map_err(EmailError::Io)?;
+        tcp.set_write_timeout(timeout).map_err(EmailError::Io)?;
+
+        let mut conn = Connection {
+            stream: BufReader::new(Stream::Plain(tcp)),
+        };
+
+        conn.expect_reply(220)?;
+        Ok(conn)
+    }
+
+    /// Wraps the connection with TLS after the server accepts a STARTTLS command.
+    fn upgrade(self, host: &str) -> Result<Connection, EmailError> {
+        let tcp = match self.stream.into_inner() {
+            Stream::Plain(tcp) => tcp,
+            stream @ Stream::Tls(_) => {
+                return Ok(Connection {
+                    stream: BufReader::new(stream),
+                })
+            }
+        };
+
+        let connector = TlsConnector::builder()
+            .and_then(|builder| builder.build())
+            .map_err(EmailError::Tls)?;
+
+        let tls = connector
+            .connect(host, tcp)
+            .map_err(|e| EmailError::TlsHandshake(e.to_string()))?;
+
+        Ok(Connection {
+            stream: BufReader::new(Stream::Tls(tls)),
+        })
+    }
+
+    /// Reads a (possibly multiline) reply and returns an error if its code isn't `code`.
+    fn expect_reply(&mut self, code: u32) -> Result<(), EmailError> {
+        let mut line = String::new();
+
+        loop {
+            line.clear();
+
+            let read = self.stream.read_line(&mut line).map_err(EmailError::Io)?;
+
+            if read == 0 {
+                return Err(EmailError::UnexpectedReply(0, "connection closed".into()));
+            }
+
+            // The last line of a reply has a space after the code instead of a dash
+            if line.len() < 4 || line.as_bytes()[3] != b'-' {
+                break;
+            }
+        }
+
+        let reply_code = line.get(..3).and_then(|c| c.parse().ok()).unwrap_or(0);
+
+        if reply_code != code {
+            let text = line.get(4..).unwrap_or("").trim().to_string();
+            return Err(EmailError::UnexpectedReply(reply_code, text));
+        }
+
+        Ok(())
+    }
+
+    fn command(&mut self, command: &str, code: u32) -> Result<(), EmailError> {
+        self.write(&format!("{}\r\n", command))?;
+        self.expect_reply(code)
+    }
+
+    fn write(&mut self, data: &str) -> Result<(), EmailError> {
+        let stream = self.stream.get_mut();
+
+        stream
+            .write_all(data.as_bytes())
+            .and_then(|_| stream.flush())
+            .map_err(EmailError::Io)
+    }
+}
+
+pub fn send(config: &EmailConfig, entries: &[Entry]) -> Result<(), EmailError> {
+    let subject = match config.digest_minutes {
+        Some(_) => format!("Broadcastify Digest ({} updates)", entries.len()),
+        None => format!("Broadcastify Update ({} feeds)", entries.len()),
+    };
+
+    send_message(config, &subject, entries)
+}
+
+/// Sends an update error or recovery on its own, even when digests are enabled. Its title is used as the subject.
+pub fn send_status(config: &EmailConfig, entry: &Entry) -> Result<(), EmailError> {
+    send_message(config, &entry.title, slice::from_ref(entry))
+}
+
+fn send_message(config: &EmailConfig, subject: &str, entries: &[Entry]) -> Result<(), EmailError> {
+    let mut conn = Connection::open(&config.host, config.port)?;
+    conn.command("EHLO localhost", 250)?;
+
+    if config.starttls {
+        conn.command("STARTTLS", 220)?;
+        conn = conn.upgrade(&config.host)?;
+        conn.command("EHLO localhost", 250)?;
+    }
+
+    if let (Some(user), Some(pass)) = (&config.username, &config.password) {
+        let token = base64::encode(&format!("\0{}\0{}", user, pass));
+        conn.command(&format!("AUTH PLAIN {}", token), 235)?;
+    }
+
+    conn.command(&format!("MAIL FROM:<{}>", config.from), 250)?;
+
+    for recipient in &config.to {
+        conn.command(&format!("RCPT TO:<{}>", recipient), 250)?;
+    }
+
+    conn.command("DATA", 354)?;
+    conn.write(&build_message(config, subject, entries))?;
+    conn.command(".", 250)?;
+
+    conn.command("QUIT", 221)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Joins the encoded words of a header back together.
+    fn decode(header: &str) -> String {
+        header
+            .split_whitespace()
+            .skip(1)
+            .map(|word| {
+                let encoded = word.trim_start_matches("=?UTF-8?B?").trim_end_matches("?=");
+                String::from_utf8(base64::decode(encoded).unwrap()).unwrap()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn non_ascii_headers_are_encoded() {
+        let subject =
+            "Évacuation à Montréal: 3 flux au-dessus de la moyenne habituelle — 120 auditeurs";
+        let header = header("Subject", &text_words(subject));
+
+        assert!(header.is_ascii());
+        assert!(header.lines().all(|line| line.len() <= MAX_HEADER_LINE));
+        assert!(header.lines().count() > 1);
+        assert_eq!(decode(&header), subject);
+    }
+
+    #[test]
+    fn long_headers_are_folded() {
+        let words = text_words(&"word ".repeat(40));
+        let header = header("Subject", &words);
+
+        assert!(header.lines().all(|line| line.len() <= MAX_HEADER_LINE));
+        assert!(header.lines().count() > 1);
+        assert_eq!(
+            header.replace("\n", ""),
+            format!("Subject: {}", words.join(" "))
+        );
+    }
+
+    /// Removes the soft line breaks and escapes from quoted-printable text.
+    fn decode_quoted_printable(text: &str) -> String {
+        let text = text.replace("=\n", "");
+        let mut bytes = Vec::new();
+        let mut rest = text.as_bytes();
+
+        while let Some((&byte, tail)) = rest.split_first() {
+            if byte == b'=' {
+                let hex = String::from_utf8(tail[..2].to_vec()).unwrap();
+                bytes.push(u8::from_str_radix(&hex, 16).unwrap());
+                rest = &tail[2..];
+            } else {
+                bytes.push(byte);
+                rest = tail;
+            }
+        }
+
+        String::from_utf8(bytes).unwrap()
+    }
+
+    #[test]
+    fn quoted_printable_keeps_the_text() {
+        let text = format!("Écoute à 100% = {} \n\tend\t\n.", "très long ".repeat(20));
+        let encoded = quoted_printable(&text);
+
+        assert!(encoded.is_ascii());
+        assert!(encoded.lines().all(|line| line.len() <= MAX_BODY_LINE));
+        assert!(encoded.lines().all(|line| !line.ends_with(' ')));
+        assert_eq!(decode_quoted_printable(&encoded), text);
+    }
+
+    #[test]
+    fn large_digests_have_short_lines() {
+        let config = EmailConfig {
+            host: "localhost".into(),
+            port: 25,
+            starttls: false,
+            username: None,
+            password: None,
+            from: "bcnotif@example.com".into(),
+            to: vec!["someone@example.com".into()],
+            digest_minutes: Some(60.0),
+        };
+
+        let entries = (0..500)
+            .map(|i| Entry {
+                time: Local::now(),
+                title: format!("Feed {} is spiking", i),
+                body: format!(
+                    "Listeners: {} — https://www.broadcastify.com/listen/feed/{}",
+                    i, i
+                ),
+                severity: Severity::Minor,
+            })
+            .collect::<Vec<_>>();
+
+        let message = build_message(&config, "Digest", &entries);
+
+        assert!(message.is_ascii());
+        assert!(message.split("\r\n").all(|line| line.len() <= 998));
+    }
+
+    #[test]
+    fn line_breaks_cant_add_headers() {
+        assert_eq!(
+            header("Subject", &text_words("Error\nBcc: someone@example.com")),
+            "Subject: Error Bcc: someone@example.com"
+        );
+    }
+}
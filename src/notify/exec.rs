@@ -0,0 +1,267 @@
+use super::template::{self, Kind, Vars};
+use super::{Event, Status};
+use config::{Backend, Config, Exec as ExecConfig};
+use feed::Feed;
+use statistics::ListenerStats;
+use std::io::{self, Read, Write};
+use std::process::{Child, Command, ExitStatus, Stdio};
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// How long to wait for the rest of a command's output after it exits.
+const OUTPUT_WAIT: Duration = Duration::from_secs(1);
+
+#[derive(Fail, Debug)]
+pub enum ExecError {
+    #[fail(display = "failed to run {}", _1)]
+    Spawn(#[cause] io::Error, String),
+
+    #[fail(display = "{}", _0)]
+    Io(#[cause] io::Error),
+
+    #[fail(display = "{} timed out after {} seconds", _0, _1)]
+    TimedOut(String, f32),
+
+    #[fail(display = "{} exited with {}: {}", _0, _1, _2)]
+    Failed(String, ExitStatus, String),
+}
+
+/// A command that is ready to be run for a feed update.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Invocation {
+    pub command: String,
+    pub args: Vec<String>,
+    pub env: Vec<(String, String)>,
+    pub input: String,
+    pub timeout: f32,
+}
+
+/// Prepares the configured command for a single feed update.
+///
+/// The feed's information is passed through environment variables and as JSON through stdin.
+pub fn prepare(
+    base_config: &Config,
+    config: &ExecConfig,
+    feed: &Feed,
+    stats: &ListenerStats,
+) -> Invocation {
+    let link = format!("http://broadcastify.com/listen/feed/{}", feed.id);
+    let jump = stats.get_jump(feed.listeners) as i32;
+    let event = Event::of(feed, stats);
+    let severity = base_config.severity.get(feed, stats).name();
+
+    let vars = Vars::for_feed(feed, stats, base_config);
+    let (title, body) = template::render(base_config, Backend::Exec, Kind::Feed(event), &vars);
+    let event = event.name();
+
+    let env = vec![
+        ("BCNOTIF_FEED_ID", feed.id.to_string()),
+        ("BCNOTIF_FEED_NAME", feed.name.clone()),
+        ("BCNOTIF_FEED_LISTENERS", feed.listeners.to_string()),
+        ("BCNOTIF_FEED_JUMP", jump.to_string()),
+        ("BCNOTIF_FEED_STATE", feed.state.abbrev.to_string()),
+        ("BCNOTIF_FEED_STATE_ID", feed.state.id.to_string()),
+        ("BCNOTIF_FEED_COUNTY", feed.county.clone()),
+        ("BCNOTIF_FEED_ALERT", feed.alert.clone().unwrap_or_default()),
+        ("BCNOTIF_FEED_URL", link.clone()),
+        ("BCNOTIF_SPIKE_COUNT", stats.spike_count.to_string()),
+        ("BCNOTIF_EVENT", event.to_string()),
+        ("BCNOTIF_SEVERITY", severity.to_string()),
+        ("BCNOTIF_TITLE", title.clone()),
+        ("BCNOTIF_BODY", body.clone()),
+    ];
+
+    let json = json!({
+        "id": feed.id,
+        "name": feed.name,
+        "listeners": feed.listeners,
+        "jump": jump,
+        "state": {
+            "id": feed.state.id,
+            "abbrev": feed.state.abbrev,
+        },
+        "county": feed.county,
+        "alert": feed.alert,
+        "url": link,
+        "spike_count": stats.spike_count,
+        "event": event,
+        "severity": severity,
+        "title": title,
+        "body": body,
+    });
+
+    Invocation {
+        command: config.command.clone(),
+        args: config.args.clone(),
+        env: env
+            .into_iter()
+            .map(|(name, value)| (name.to_string(), value))
+            .collect(),
+        input: json.to_string(),
+        timeout: config.timeout,
+    }
+}
+
+/// Prepares the configured command for an update error or recovery.
+///
+/// Only the event, severity, title, body, error and number of failures are passed to it.
+pub fn prepare_status(base_config: &Config, config: &ExecConfig, status: &Status) -> Invocation {
+    let event = status.kind().name();
+    let severity = status.severity().name();
+    let (title, body) = status.render(base_config, Backend::Exec);
+
+    let mut env = vec![
+        ("BCNOTIF_EVENT", event.to_string()),
+        ("BCNOTIF_SEVERITY", severity.to_string()),
+        ("BCNOTIF_TITLE", title.clone()),
+        ("BCNOTIF_BODY", body.clone()),
+        ("BCNOTIF_FAILURES", status.failures.to_string()),
+    ];
+
+    if let Some(error) = status.error {
+        env.push(("BCNOTIF_ERROR", error.to_string()));
+    }
+
+    let json = json!({
+        "event": event,
+        "severity": severity,
+        "title": title,
+        "body": body,
+        "error": status.error,
+        "failures": status.failures,
+    });
+
+    Invocation {
+        command: config.command.clone(),
+        args: config.args.clone(),
+        env: env
+            .into_iter()
+            .map(|(name, value)| (name.to_string(), value))
+            .collect(),
+        input: json.to_string(),
+        timeout: config.timeout,
+    }
+}
+
+impl Invocation {
+    pub fn run(&self) -> Result<(), ExecError> {
+        let mut child = Command::new(&self.command)
+            .args(&self.args)
+            .envs(self.env.iter().map(|(name, value)| (name, value)))
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| ExecError::Spawn(e, self.command.clone()))?;
+
+        if let Some(mut stdin) = child.stdin.take() {
+            // The command is free to ignore its input, so a broken pipe isn't an error
+            match stdin.write_all(self.input.as_bytes()) {
+                Err(ref e) if e.kind() == io::ErrorKind::BrokenPipe => (),
+                result => result.map_err(ExecError::Io)?,
+            }
+        }
+
+        // The output has to be read while the command is running so it can't block
+        // on a full pipe
+        let stdout = capture(child.stdout.take());
+        let stderr = capture(child.stderr.take());
+
+        let status = wait_timeout(&mut child, self.timeout).map_err(ExecError::Io)?;
+
+        // Processes started by the command can keep its pipes open after it exits or is killed,
+        // so the output is only waited on for a little while before the readers are left behind
+        let deadline = Instant::now() + OUTPUT_WAIT;
+        let stdout = receive_until(&stdout, deadline);
+        let stderr = receive_until(&stderr, deadline);
+
+        for line in stdout.lines() {
+            println!("[{}] {}", self.command, line);
+        }
+
+        match status {
+            Some(status) if status.success() => Ok(()),
+            Some(status) => Err(ExecError::Failed(
+                self.command.clone(),
+                status,
+                stderr.trim().to_string(),
+            )),
+            None => Err(ExecError::TimedOut(self.command.clone(), self.timeout)),
+        }
+    }
+}
+
+/// Reads a pipe to the end on another thread, and sends the output once it's done.
+fn capture<R>(pipe: Option<R>) -> Receiver<String>
+where
+    R: Read + Send + 'static,
+{
+    let (sender, receiver) = mpsc::channel();
+
+    thread::spawn(move || {
+        let mut output = String::new();
+
+        if let Some(mut pipe) = pipe {
+            pipe.read_to_string(&mut output).ok();
+        }
+
+        sender.send(output).ok();
+    });
+
+    receiver
+}
+
+/// Gets the output of a pipe, or an empty string if it hasn't been closed by the deadline.
+fn receive_until(output: &Receiver<String>, deadline: Instant) -> String {
+    let now = Instant::now();
+    let remaining = if deadline > now {
+        deadline - now
+    } else {
+        Duration::from_secs(0)
+    };
+
+    output.recv_timeout(remaining).unwrap_or_default()
+}
+
+/// Waits for the child to exit, and kills it if it takes longer than `timeout` seconds.
+/// Returns `None` if the child was killed.
+fn wait_timeout(child: &mut Child, timeout: f32) -> io::Result<Option<ExitStatus>> {
+    let start = Instant::now();
+    let timeout = Duration::from_millis((timeout * 1000.0) as u64);
+
+    loop {
+        if let Some(status) = child.try_wait()? {
+            return Ok(Some(status));
+        }
+
+        if start.elapsed() >= timeout {
+            child.kill()?;
+            child.wait()?;
+            return Ok(None);
+        }
+
+        thread::sleep(Duration::from_millis(50));
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn processes_left_running_dont_block_the_command() {
+        let invocation = Invocation {
+            command: "sh".into(),
+            args: vec!["-c".into(), "sleep 10 & echo started".into()],
+            env: Vec::new(),
+            input: String::new(),
+            timeout: 5.0,
+        };
+
+        let start = Instant::now();
+
+        assert!(invocation.run().is_ok());
+        assert!(start.elapsed() < Duration::from_secs(5));
+    }
+}
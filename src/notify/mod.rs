@@ -0,0 +1,1044 @@
+mod action;
+mod email;
+mod exec;
+mod mqtt;
+mod outbox;
+mod template;
+
+use self::action::FeedActions;
+use self::outbox::Delivery;
+use self::template::{Kind, Vars};
+use chrono::Utc;
+use config::{Backend, Config, ScheduleWindow, Severity, Urgency};
+use error;
+use feed::Feed;
+use mute::MuteList;
+use statistics::ListenerStats;
+use std::collections::HashMap;
+use std::mem;
+use std::sync::{Arc, Mutex};
+
+pub use self::outbox::{Outbox, OutboxError};
+
+#[derive(Fail, Debug)]
+pub enum NotifyError {
+    #[cfg(any(unix, macos))]
+    #[fail(display = "failed to create notification")]
+    CreationFailed,
+
+    #[cfg(windows)]
+    #[fail(display = "{:?}", _0)]
+    WinRT(::winrt::Error),
+
+    #[fail(display = "failed to send email notification")]
+    Email(#[cause] email::EmailError),
+
+    #[fail(display = "failed to run notification command")]
+    Exec(#[cause] exec::ExecError),
+
+    #[fail(display = "failed to publish MQTT messages")]
+    Mqtt(#[cause] mqtt::MqttError),
+
+    #[fail(display = "failed to save notification outbox")]
+    Outbox(#[cause] outbox::OutboxError),
+}
+
+/// Controls how a desktop notification is presented.
+pub struct Style {
+    pub icon: String,
+    pub urgency: Urgency,
+    /// The time in seconds before the notification expires. Zero means that it never expires,
+    /// and `None` leaves it up to the notification server.
+    pub timeout: Option<f32>,
+    pub sound: Option<String>,
+}
+
+impl Style {
+    pub fn for_severity(severity: Severity, config: &Config) -> Style {
+        let severities = &config.severity;
+
+        let (style, urgency, icon) = match severity {
+            Severity::Minor => (&severities.minor, Urgency::Low, "emblem-sound"),
+            Severity::Major => (&severities.major, Urgency::Normal, "emblem-sound"),
+            Severity::Critical => (&severities.critical, Urgency::Critical, "dialog-warning"),
+        };
+
+        Style {
+            icon: style.icon.clone().unwrap_or_else(|| icon.to_string()),
+            urgency: style.urgency.unwrap_or(urgency),
+            timeout: style.timeout,
+            sound: style.sound.clone(),
+        }
+    }
+
+    pub fn error() -> Style {
+        Style {
+            icon: "dialog-error".to_string(),
+            urgency: Urgency::Normal,
+            timeout: None,
+            sound: None,
+        }
+    }
+}
+
+/// Returns the highest of the two severities. Meant to be used with `Iterator::fold`.
+pub fn max_severity(highest: Option<Severity>, severity: Severity) -> Option<Severity> {
+    match highest {
+        Some(highest) if highest >= severity => Some(highest),
+        _ => Some(severity),
+    }
+}
+
+#[cfg(any(unix, macos))]
+mod unix {
+    extern crate dbus;
+    extern crate notify_rust;
+
+    use self::dbus::{BusType, Connection, ConnectionItem, Message};
+    use self::notify_rust::{
+        Notification, NotificationHandle, NotificationHint, NotificationUrgency,
+    };
+    use super::action::Action;
+    use super::*;
+    use std::collections::VecDeque;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::mpsc::{self, Receiver, Sender, TryRecvError};
+    use std::thread;
+
+    /// The most notifications that actions are listened for at once. Notifications that never
+    /// expire would otherwise be listened for forever, so the oldest one is dropped after this.
+    const MAX_LISTENED: usize = 50;
+
+    /// How long to wait for a D-Bus signal before checking for new notifications, in milliseconds.
+    const POLL_TIME: i32 = 250;
+
+    const INTERFACE: &str = "org.freedesktop.Notifications";
+
+    enum Request {
+        /// Shows a notification with actions, and sends its ID back once it's shown.
+        Show(Box<Notification>, FeedActions, Sender<Option<u32>>),
+        /// Stops listening to a notification that was replaced by one without actions.
+        Forget(u32),
+    }
+
+    lazy_static! {
+        /// Sends requests to the thread that shows notifications with actions and listens for them.
+        /// The thread is only started once it's needed.
+        static ref LISTENER: Mutex<Sender<Request>> = {
+            let (tx, rx) = mpsc::channel();
+            thread::spawn(move || listen(&rx));
+            STARTED.store(true, Ordering::SeqCst);
+            Mutex::new(tx)
+        };
+    }
+
+    /// Set once the listening thread has started, so notifications without actions don't start it
+    /// just to tell it to forget about them.
+    static STARTED: AtomicBool = AtomicBool::new(false);
+
+    /// A notification that is being listened to for actions.
+    struct Listened {
+        id: u32,
+        /// Some notification servers close a notification when the connection it was shown
+        /// with closes, so the handle that owns it is kept until the notification closes.
+        _handle: NotificationHandle,
+        actions: FeedActions,
+    }
+
+    /// Handles requests to show notifications with actions, and listens for the actions of every one
+    /// of them with a single connection.
+    fn listen(requests: &Receiver<Request>) {
+        let connection = Connection::get_private(BusType::Session).and_then(|connection| {
+            connection
+                .add_match("interface='org.freedesktop.Notifications',member='ActionInvoked'")?;
+            connection.add_match(
+                "interface='org.freedesktop.Notifications',member='NotificationClosed'",
+            )?;
+            Ok(connection)
+        });
+
+        let connection = match connection {
+            Ok(connection) => Some(connection),
+            Err(err) => {
+                eprintln!("failed to listen for notification actions: {}", err);
+                None
+            }
+        };
+
+        let mut listened = VecDeque::new();
+
+        loop {
+            let request = match connection {
+                Some(_) => requests.try_recv(),
+                None => requests.recv().map_err(|_| TryRecvError::Disconnected),
+            };
+
+            match request {
+                Ok(Request::Show(notification, actions, id_tx)) => {
+                    let id = show(&notification, actions, &mut listened, connection.is_some());
+                    id_tx.send(id).ok();
+                    continue;
+                }
+                Ok(Request::Forget(id)) => {
+                    listened.retain(|entry: &Listened| entry.id != id);
+                    continue;
+                }
+                Err(TryRecvError::Empty) => (),
+                Err(TryRecvError::Disconnected) => return,
+            }
+
+            if let Some(ref connection) = connection {
+                if let Some(ConnectionItem::Signal(msg)) = connection.iter(POLL_TIME).next() {
+                    handle_signal(&msg, &mut listened);
+                }
+            }
+        }
+    }
+
+    /// Shows a notification and starts listening to it if actions can be listened for. Returns the
+    /// ID of the notification.
+    fn show(
+        notification: &Notification,
+        actions: FeedActions,
+        listened: &mut VecDeque<Listened>,
+        can_listen: bool,
+    ) -> Option<u32> {
+        let handle = notification.show().ok()?;
+        let id = handle.id();
+
+        // Replacing a notification doesn't close it, so only the actions of the newest one should apply
+        listened.retain(|entry| entry.id != id);
+
+        if !can_listen {
+            return Some(id);
+        }
+
+        if listened.len() >= MAX_LISTENED {
+            listened.pop_front();
+        }
+
+        listened.push_back(Listened {
+            id,
+            _handle: handle,
+            actions,
+        });
+
+        Some(id)
+    }
+
+    /// Invokes the action that was picked, or stops listening to a notification that was closed.
+    fn handle_signal(msg: &Message, listened: &mut VecDeque<Listened>) {
+        let (_, _, interface, member) = msg.headers();
+
+        if interface.as_deref() != Some(INTERFACE) {
+            return;
+        }
+
+        let (id, action) = match member.as_deref() {
+            Some("ActionInvoked") => match msg.get2::<u32, &str>() {
+                (Some(id), Some(action)) => (id, Some(action.to_string())),
+                _ => return,
+            },
+            Some("NotificationClosed") => match msg.get1::<u32>() {
+                Some(id) => (id, None),
+                None => return,
+            },
+            _ => return,
+        };
+
+        let entry = match listened.iter().position(|entry| entry.id == id) {
+            Some(i) => listened.remove(i),
+            None => return,
+        };
+
+        if let (Some(entry), Some(action)) = (entry, action) {
+            let actions = entry.actions;
+
+            // Actions can run commands, which shouldn't hold up the actions of other notifications
+            thread::spawn(move || actions.invoke(&action));
+        }
+    }
+
+    /// Shows a notification and returns its ID. If `replaces` is set, the notification with
+    /// that ID will be updated instead of a new one being created.
+    pub fn create(
+        style: &Style,
+        title: &str,
+        body: &str,
+        replaces: Option<u32>,
+        actions: Option<FeedActions>,
+    ) -> Result<Option<u32>, NotifyError> {
+        let urgency = match style.urgency {
+            Urgency::Low => NotificationUrgency::Low,
+            Urgency::Normal => NotificationUrgency::Normal,
+            Urgency::Critical => NotificationUrgency::Critical,
+        };
+
+        let mut notification = Notification::new();
+
+        notification
+            .summary(title)
+            .body(body)
+            .icon(&style.icon)
+            .urgency(urgency);
+
+        if let Some(timeout) = style.timeout {
+            notification.timeout((timeout * 1000.0) as i32);
+        }
+
+        if let Some(ref sound) = style.sound {
+            notification.hint(NotificationHint::SoundName(sound.clone()));
+        }
+
+        if let Some(id) = replaces {
+            notification.id(id);
+        }
+
+        let actions = match actions {
+            Some(actions) => actions,
+            None => {
+                let id = notification
+                    .show()
+                    .map_err(|_| NotifyError::CreationFailed)?
+                    .id();
+
+                if replaces.is_some() && STARTED.load(Ordering::SeqCst) {
+                    LISTENER.lock().unwrap().send(Request::Forget(id)).ok();
+                }
+
+                return Ok(Some(id));
+            }
+        };
+
+        for &action in &Action::ALL {
+            notification.action(action.id(), &actions.label(action));
+        }
+
+        let (tx, rx) = mpsc::channel();
+
+        LISTENER
+            .lock()
+            .unwrap()
+            .send(Request::Show(Box::new(notification), actions, tx))
+            .map_err(|_| NotifyError::CreationFailed)?;
+
+        match rx.recv() {
+            Ok(Some(id)) => Ok(Some(id)),
+            _ => Err(NotifyError::CreationFailed),
+        }
+    }
+}
+
+#[cfg(windows)]
+mod windows {
+    use super::{FeedActions, NotifyError, Style};
+    use winrt::windows::data::xml::dom::*;
+    use winrt::windows::ui::notifications::*;
+    use winrt::FastHString;
+
+    // The purpose of having an inner create function is so that we only have to specify the error
+    // type once if creation fails
+    fn inner_create(title: &str, body: &str) -> Result<(), ::winrt::Error> {
+        unsafe {
+            let toast_xml =
+                ToastNotificationManager::get_template_content(ToastTemplateType::ToastText02)?;
+
+            let toast_text_elements =
+                toast_xml.get_elements_by_tag_name(&FastHString::new("text"))?;
+
+            let add_text = |i, string| {
+                let node = &*toast_xml
+                    .create_text_node(&FastHString::new(string))?
+                    .query_interface::<IXmlNode>()
+                    .unwrap();
+
+                toast_text_elements.item(i)?.append_child(node)
+            };
+
+            add_text(0, title)?;
+            add_text(1, body)?;
+
+            let toast = ToastNotification::create_toast_notification(&*toast_xml)?;
+            let id = env!("CARGO_PKG_NAME");
+
+            ToastNotificationManager::create_toast_notifier_with_id(&FastHString::new(id))?
+                .show(&*toast)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn create(
+        _: &Style,
+        title: &str,
+        body: &str,
+        _: Option<u32>,
+        _: Option<FeedActions>,
+    ) -> Result<Option<u32>, NotifyError> {
+        inner_create(title, body).map_err(NotifyError::WinRT)?;
+        Ok(None)
+    }
+}
+
+#[cfg(any(unix, macos))]
+use self::unix::create;
+
+#[cfg(windows)]
+use self::windows::create;
+
+/// The reason a feed is being shown.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Event {
+    Spike,
+    Alert,
+    IncidentEnded,
+}
+
+impl Event {
+    pub fn of(feed: &Feed, stats: &ListenerStats) -> Event {
+        if stats.has_spiked {
+            Event::Spike
+        } else if stats.incident_ended {
+            Event::IncidentEnded
+        } else if feed.alert.is_some() {
+            Event::Alert
+        } else {
+            Event::Spike
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Event::Spike => "spike",
+            Event::Alert => "alert",
+            Event::IncidentEnded => "incident ended",
+        }
+    }
+}
+
+/// Dispatches feed updates to every enabled notification backend.
+pub struct Notifier {
+    desktop: DesktopNotifier,
+    email: email::EmailNotifier,
+    mqtt: mqtt::MqttNotifier,
+    suppressed: Suppressed,
+    outbox: Outbox,
+}
+
+impl Notifier {
+    pub fn new(mutes: Arc<Mutex<MuteList>>, outbox: Outbox) -> Notifier {
+        Notifier {
+            desktop: DesktopNotifier::new(mutes),
+            email: email::EmailNotifier::new(),
+            mqtt: mqtt::MqttNotifier::new(),
+            suppressed: Suppressed::new(),
+            outbox,
+        }
+    }
+
+    /// Records the current state of a feed for backends that report on every feed,
+    /// rather than only ones that have spiked.
+    pub fn record_stats(&mut self, feed: &Feed, stats: &ListenerStats, config: &Config) {
+        if let Some(ref mqtt) = config.mqtt {
+            if mqtt.publish_stats {
+                self.mqtt.queue_stats(mqtt, feed, stats);
+            }
+        }
+    }
+
+    /// Sends the specified feeds to each backend that the current schedule window allows,
+    /// after retrying any earlier notifications that failed.
+    ///
+    /// Every notification is attempted, even if a previous one fails. Failed notifications
+    /// are printed and saved to the outbox to be retried on a later update, so an error is only
+    /// returned when the outbox can't be saved.
+    pub fn show_updates(
+        &mut self,
+        feeds: Vec<(Feed, ListenerStats)>,
+        config: &Config,
+    ) -> Result<(), NotifyError> {
+        let now = Utc::now();
+        let had_entries = !self.outbox.entries.is_empty();
+        let mut failed = self.retry_outbox(config);
+
+        let window = config.get_schedule_window();
+        let min_severity = window.map_or(Severity::Minor, |(_, window)| window.min_severity);
+
+        let (feeds, suppressed): (Vec<_>, Vec<_>) = feeds
+            .into_iter()
+            .partition(|(feed, stats)| config.severity.get(feed, stats) >= min_severity);
+
+        let allows = |backend| match window {
+            Some((_, window)) => window.allows(backend),
+            None => true,
+        };
+
+        let mut failures = Vec::new();
+
+        // Feeds that can't be shown on the desktop during the window are summarized along with the suppressed ones
+        let mut hidden = suppressed.iter().collect::<Vec<_>>();
+
+        if !allows(Backend::Desktop) {
+            hidden.extend(&feeds);
+        }
+
+        let summary = self
+            .suppressed
+            .update(window, &hidden, allows(Backend::Desktop), config);
+
+        if let Some(msg) = summary {
+            if let Err(err) = msg.show(None, None, config) {
+                failures.push((err, Delivery::Desktop(msg)));
+            }
+        }
+
+        if allows(Backend::Desktop) {
+            failures.extend(self.desktop.show(&feeds, config));
+        }
+
+        if allows(Backend::Exec) {
+            for exec in &config.exec {
+                for (feed, stats) in &feeds {
+                    let invocation = exec::prepare(config, exec, feed, stats);
+
+                    if let Err(err) = invocation.run() {
+                        failures.push((NotifyError::Exec(err), Delivery::Exec(invocation)));
+                    }
+                }
+            }
+        }
+
+        if let Some(ref mqtt) = config.mqtt {
+            if allows(Backend::Mqtt) {
+                let events = mqtt::events(config, mqtt, &feeds);
+
+                if let Err(err) = self.mqtt.publish(config, mqtt, &events) {
+                    let err = NotifyError::Mqtt(err);
+                    let mut events = events.into_iter();
+
+                    // Every event shares the same error, so only the first one reports it
+                    if let Some((topic, payload)) = events.next() {
+                        failures.push((err, Delivery::Mqtt(topic, payload)));
+                    }
+
+                    for (topic, payload) in events {
+                        self.outbox
+                            .retry_later(Delivery::Mqtt(topic, payload), 1, now, config);
+                    }
+                }
+            } else if let Err(err) = self.mqtt.keep_alive(config, mqtt) {
+                // There's nothing to retry, so the connection is just opened again on the next update
+                error::print(&NotifyError::Mqtt(err).into());
+            }
+        }
+
+        if let Some(ref email) = config.email {
+            if allows(Backend::Email) {
+                if let Some(entries) = self.email.queue(config, email, &feeds) {
+                    if let Err(err) = email::send(email, &entries) {
+                        failures.push((NotifyError::Email(err), Delivery::Email(entries)));
+                    }
+                }
+            }
+        }
+
+        for (err, delivery) in failures {
+            error::print(&err.into());
+            self.outbox.retry_later(delivery, 1, now, config);
+            failed += 1;
+        }
+
+        if had_entries || !self.outbox.entries.is_empty() {
+            self.outbox.save().map_err(NotifyError::Outbox)?;
+        }
+
+        // The feeds were still checked, so failed deliveries don't count as a failed update
+        if failed > 0 {
+            eprintln!(
+                "{} notifications failed to be delivered and were queued to be retried",
+                failed
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Sends an update error or recovery through each backend that the current schedule window allows,
+    /// and returns the error of every backend that failed. They aren't retried, since another error is
+    /// sent if updates keep failing.
+    pub fn show_status(&mut self, status: &Status, config: &Config) -> Vec<NotifyError> {
+        let window = config.get_schedule_window();
+
+        let allows = |backend| match window {
+            Some((_, window)) => window.allows(backend),
+            None => true,
+        };
+
+        let mut failures = Vec::new();
+
+        if allows(Backend::Desktop) {
+            if let Err(err) = create_status(status, config) {
+                failures.push(err);
+            }
+        }
+
+        if allows(Backend::Exec) {
+            for exec in &config.exec {
+                if let Err(err) = exec::prepare_status(config, exec, status).run() {
+                    failures.push(NotifyError::Exec(err));
+                }
+            }
+        }
+
+        if let Some(ref mqtt) = config.mqtt {
+            if allows(Backend::Mqtt) {
+                let event = mqtt::status_event(config, mqtt, status);
+
+                if let Err(err) = self.mqtt.publish(config, mqtt, &[event]) {
+                    failures.push(NotifyError::Mqtt(err));
+                }
+            }
+        }
+
+        if let Some(ref email) = config.email {
+            if allows(Backend::Email) {
+                let entry = email::Entry::status(config, status);
+
+                if let Err(err) = email::send_status(email, &entry) {
+                    failures.push(NotifyError::Email(err));
+                }
+            }
+        }
+
+        failures
+    }
+
+    /// Attempts every outbox entry that is ready to be retried. Once a backend fails,
+    /// the rest of its entries are left for the next update. Returns the number of
+    /// entries that failed.
+    fn retry_outbox(&mut self, config: &Config) -> usize {
+        let mut failed_backends = Vec::new();
+        let mut failed = 0;
+
+        for entry in self.outbox.take_due(config) {
+            let backend = entry.delivery.backend();
+
+            if failed_backends.contains(&backend) {
+                self.outbox.put_back(entry);
+                continue;
+            }
+
+            if let Err(err) = self.deliver(&entry.delivery, config) {
+                error::print(&err.into());
+                failed_backends.push(backend);
+                failed += 1;
+
+                if !self.outbox.retry_later(
+                    entry.delivery,
+                    entry.attempts + 1,
+                    entry.created,
+                    config,
+                ) {
+                    eprintln!(
+                        "giving up on notification after {} attempts",
+                        entry.attempts + 1
+                    );
+                }
+            }
+        }
+
+        failed
+    }
+
+    fn deliver(&mut self, delivery: &Delivery, config: &Config) -> Result<(), NotifyError> {
+        match *delivery {
+            Delivery::Desktop(ref msg) => msg.show(None, None, config).map(|_| ()),
+            Delivery::Exec(ref invocation) => invocation.run().map_err(NotifyError::Exec),
+            // Deliveries for backends that have since been removed from the config are dropped
+            Delivery::Email(ref entries) => match config.email {
+                Some(ref email) => email::send(email, entries).map_err(NotifyError::Email),
+                None => Ok(()),
+            },
+            Delivery::Mqtt(ref topic, ref payload) => match config.mqtt {
+                Some(ref mqtt) => self
+                    .mqtt
+                    .publish(config, mqtt, &[(topic.clone(), payload.clone())])
+                    .map_err(NotifyError::Mqtt),
+                None => Ok(()),
+            },
+        }
+    }
+}
+
+/// Collects the feeds that were suppressed by a schedule window so they can be summarized
+/// once the window ends.
+struct Suppressed {
+    window: Option<usize>,
+    /// The summary line of each feed suppressed during the current window. Only the latest one is kept for every feed.
+    lines: Vec<(u32, String)>,
+    /// The summary lines from windows that have ended, which are kept until desktop notifications are allowed.
+    ended: Vec<(u32, String)>,
+}
+
+impl Suppressed {
+    fn new() -> Suppressed {
+        Suppressed {
+            window: None,
+            lines: Vec::new(),
+            ended: Vec::new(),
+        }
+    }
+
+    /// Records the specified suppressed feeds, and returns a summary of every feed that was
+    /// suppressed during earlier windows if desktop notifications can be shown.
+    fn update(
+        &mut self,
+        window: Option<(usize, &ScheduleWindow)>,
+        feeds: &[&(Feed, ListenerStats)],
+        show_desktop: bool,
+        config: &Config,
+    ) -> Option<DesktopMessage> {
+        let index = window.map(|(i, _)| i);
+
+        if index != self.window {
+            for (id, line) in self.lines.drain(..) {
+                set_line(&mut self.ended, id, line);
+            }
+
+            self.window = index;
+        }
+
+        let summary = if show_desktop && !self.ended.is_empty() {
+            let summary = render_suppressed_summary(&self.ended, config);
+            self.ended.clear();
+            Some(summary)
+        } else {
+            None
+        };
+
+        match window {
+            Some((_, window)) if window.summarize => (),
+            _ => return summary,
+        }
+
+        for (feed, stats) in feeds {
+            let vars = Vars::for_feed(feed, stats, config);
+            let line = template::render_summary_line(config, Backend::Desktop, &vars);
+
+            set_line(&mut self.lines, feed.id, line);
+        }
+
+        summary
+    }
+}
+
+/// Replaces the summary line of a feed, or adds it if the feed doesn't have one yet.
+fn set_line(lines: &mut Vec<(u32, String)>, id: u32, line: String) {
+    match lines.iter().position(|&(line_id, _)| line_id == id) {
+        Some(i) => lines[i].1 = line,
+        None => lines.push((id, line)),
+    }
+}
+
+/// Identifies a desktop notification that can be updated in place.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum Slot {
+    Feed(u32),
+    Group(String),
+}
+
+/// Shows feed updates as desktop notifications.
+struct DesktopNotifier {
+    mutes: Arc<Mutex<MuteList>>,
+    /// The notifications that were shown during the last update. Feeds and groups that
+    /// are shown again on the next update replace their existing notification.
+    shown: HashMap<Slot, u32>,
+}
+
+impl DesktopNotifier {
+    fn new(mutes: Arc<Mutex<MuteList>>) -> DesktopNotifier {
+        DesktopNotifier {
+            mutes,
+            shown: HashMap::new(),
+        }
+    }
+
+    /// Shows the specified feeds and returns every notification that failed to be shown.
+    fn show(
+        &mut self,
+        feeds: &[(Feed, ListenerStats)],
+        config: &Config,
+    ) -> Vec<(NotifyError, Delivery)> {
+        use config::DisplayMode;
+
+        // Only notifications from consecutive updates are replaced, so anything that
+        // isn't shown again is forgotten
+        let mut last_shown = mem::take(&mut self.shown);
+
+        if !config.misc.update_in_place {
+            last_shown.clear();
+        }
+
+        if feeds.is_empty() {
+            return Vec::new();
+        }
+
+        match config.misc.display_mode {
+            DisplayMode::Individual => self.show_individual(feeds, &mut last_shown, config),
+            DisplayMode::Summary => self.show_summary(feeds, &mut last_shown, config),
+            DisplayMode::ByState => self.show_grouped(
+                feeds,
+                |feed| feed.state.abbrev.to_string(),
+                &mut last_shown,
+                config,
+            ),
+            DisplayMode::ByCounty => self.show_grouped(
+                feeds,
+                |feed| format!("{}, {}", feed.county, feed.state.abbrev),
+                &mut last_shown,
+                config,
+            ),
+        }
+    }
+
+    /// Shows a notification for each feed, along with the actions that can be taken on it.
+    fn show_individual(
+        &mut self,
+        feeds: &[(Feed, ListenerStats)],
+        last_shown: &mut HashMap<Slot, u32>,
+        config: &Config,
+    ) -> Vec<(NotifyError, Delivery)> {
+        let mut failures = Vec::new();
+        let total = feeds.len() as i32;
+
+        for (i, (feed, stats)) in feeds.iter().enumerate() {
+            let slot = Slot::Feed(feed.id);
+
+            let actions = if config.actions.enabled {
+                Some(FeedActions::new(feed, config, self.mutes.clone()))
+            } else {
+                None
+            };
+
+            let msg = render_update(1 + i as i32, total, feed, stats, config);
+            let replaces = last_shown.remove(&slot);
+
+            match msg.show(replaces, actions, config) {
+                Ok(id) => self.remember(slot, id),
+                Err(err) => failures.push((err, Delivery::Desktop(msg))),
+            }
+        }
+
+        failures
+    }
+
+    /// Shows a single notification that lists every feed.
+    fn show_summary(
+        &mut self,
+        feeds: &[(Feed, ListenerStats)],
+        last_shown: &mut HashMap<Slot, u32>,
+        config: &Config,
+    ) -> Vec<(NotifyError, Delivery)> {
+        self.show_group("All".to_string(), feeds.iter(), last_shown, config)
+            .into_iter()
+            .collect()
+    }
+
+    /// Shows a notification for each group of feeds that share the same key.
+    fn show_grouped<F>(
+        &mut self,
+        feeds: &[(Feed, ListenerStats)],
+        key_fn: F,
+        last_shown: &mut HashMap<Slot, u32>,
+        config: &Config,
+    ) -> Vec<(NotifyError, Delivery)>
+    where
+        F: Fn(&Feed) -> String,
+    {
+        // Groups are kept in the order their first feed appears in so the configured
+        // sorting still applies to them
+        let mut groups: Vec<(String, Vec<&(Feed, ListenerStats)>)> = Vec::new();
+
+        for entry in feeds {
+            let key = key_fn(&entry.0);
+
+            match groups.iter().position(|group| group.0 == key) {
+                Some(i) => groups[i].1.push(entry),
+                None => groups.push((key, vec![entry])),
+            }
+        }
+
+        groups
+            .into_iter()
+            .filter_map(|(name, group)| self.show_group(name, group, last_shown, config))
+            .collect()
+    }
+
+    /// Shows a notification that lists the specified feeds, and returns the failure if it couldn't be shown.
+    fn show_group<'a, I>(
+        &mut self,
+        name: String,
+        feeds: I,
+        last_shown: &mut HashMap<Slot, u32>,
+        config: &Config,
+    ) -> Option<(NotifyError, Delivery)>
+    where
+        I: IntoIterator<Item = &'a (Feed<'a>, ListenerStats)>,
+    {
+        let msg = render_summary(&name, feeds, config);
+        let slot = Slot::Group(name);
+        let replaces = last_shown.remove(&slot);
+
+        match msg.show(replaces, None, config) {
+            Ok(id) => {
+                self.remember(slot, id);
+                None
+            }
+            Err(err) => Some((err, Delivery::Desktop(msg))),
+        }
+    }
+
+    fn remember(&mut self, slot: Slot, id: Option<u32>) {
+        if let Some(id) = id {
+            self.shown.insert(slot, id);
+        }
+    }
+}
+
+/// A desktop notification that has been rendered, but not necessarily shown.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DesktopMessage {
+    pub title: String,
+    pub body: String,
+    pub severity: Severity,
+}
+
+impl DesktopMessage {
+    /// Shows the notification and returns its ID, if the platform has one.
+    pub fn show(
+        &self,
+        replaces: Option<u32>,
+        actions: Option<FeedActions>,
+        config: &Config,
+    ) -> Result<Option<u32>, NotifyError> {
+        let style = Style::for_severity(self.severity, config);
+        create(&style, &self.title, &self.body, replaces, actions)
+    }
+}
+
+fn render_update(
+    index: i32,
+    max_index: i32,
+    feed: &Feed,
+    feed_stats: &ListenerStats,
+    config: &Config,
+) -> DesktopMessage {
+    let vars = Vars::for_feed(feed, feed_stats, config)
+        .set("index", index)
+        .set("total", max_index);
+
+    let kind = Kind::Feed(Event::of(feed, feed_stats));
+    let (title, body) = template::render(config, Backend::Desktop, kind, &vars);
+
+    DesktopMessage {
+        title,
+        body,
+        severity: config.severity.get(feed, feed_stats),
+    }
+}
+
+/// Renders a single notification that lists every specified feed.
+fn render_summary<'a, I>(group: &str, feeds: I, config: &Config) -> DesktopMessage
+where
+    I: IntoIterator<Item = &'a (Feed<'a>, ListenerStats)>,
+{
+    let mut severity = None;
+
+    let lines = feeds
+        .into_iter()
+        .map(|(feed, stats)| {
+            severity = max_severity(severity, config.severity.get(feed, stats));
+
+            let vars = Vars::for_feed(feed, stats, config);
+            template::render_summary_line(config, Backend::Desktop, &vars)
+        })
+        .collect::<Vec<_>>();
+
+    let vars = Vars::new().set("group", group).set("total", lines.len());
+
+    // The summary takes on the style of its most severe feed
+    DesktopMessage {
+        title: template::render_summary_title(config, Backend::Desktop, &vars),
+        body: lines.join("\n"),
+        severity: severity.unwrap_or(Severity::Minor),
+    }
+}
+
+/// Renders a notification that lists every feed that was suppressed during a schedule window.
+fn render_suppressed_summary(lines: &[(u32, String)], config: &Config) -> DesktopMessage {
+    let vars = Vars::new()
+        .set("group", "Suppressed")
+        .set("total", lines.len());
+
+    let body = lines
+        .iter()
+        .map(|(_, line)| line.as_str())
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    DesktopMessage {
+        title: template::render_summary_title(config, Backend::Desktop, &vars),
+        body,
+        severity: Severity::Minor,
+    }
+}
+
+/// An update error, or updates working again after failing, to send through every backend.
+pub struct Status<'a> {
+    /// The error message, or `None` when updates have recovered.
+    pub error: Option<&'a str>,
+    pub failures: u32,
+}
+
+impl<'a> Status<'a> {
+    fn kind(&self) -> Kind {
+        match self.error {
+            Some(_) => Kind::Error,
+            None => Kind::Recovered,
+        }
+    }
+
+    fn severity(&self) -> Severity {
+        match self.error {
+            Some(_) => Severity::Major,
+            None => Severity::Minor,
+        }
+    }
+
+    fn vars(&self) -> Vars {
+        let vars = Vars::new().set("failures", self.failures);
+
+        match self.error {
+            Some(error) => vars.set("error", error),
+            None => vars,
+        }
+    }
+
+    /// Renders the title and body of the notification for the specified backend.
+    fn render(&self, config: &Config, backend: Backend) -> (String, String) {
+        template::render(config, backend, self.kind(), &self.vars())
+    }
+}
+
+/// Shows an update error or recovery as a desktop notification.
+pub fn create_status(status: &Status, config: &Config) -> Result<(), NotifyError> {
+    let (title, body) = status.render(config, Backend::Desktop);
+
+    let style = match status.error {
+        Some(_) => Style::error(),
+        None => Style::for_severity(Severity::Minor, config),
+    };
+
+    create(&style, &title, &body, None, None)?;
+    Ok(())
+}
@@ -0,0 +1,381 @@
+use super::template::{self, Kind, Vars};
+use super::{Event, Status};
+use config::{Backend, Config, Mqtt as MqttConfig};
+use feed::Feed;
+use statistics::ListenerStats;
+use std::io::{self, Read, Write};
+use std::net::TcpStream;
+use std::time::Duration;
+
+#[derive(Fail, Debug)]
+pub enum MqttError {
+    #[fail(display = "{}", _0)]
+    Io(#[cause] io::Error),
+
+    #[fail(display = "broker refused connection with code {}", _0)]
+    ConnectionRefused(u8),
+
+    #[fail(display = "received unexpected packet type {:#x}", _0)]
+    UnexpectedPacket(u8),
+
+    #[fail(display = "received packet with a remaining length longer than 4 bytes")]
+    InvalidLength,
+}
+
+mod packet {
+    pub const CONNECT: u8 = 0x10;
+    pub const CONNACK: u8 = 0x20;
+    pub const PUBLISH: u8 = 0x30;
+    pub const PUBACK: u8 = 0x40;
+    pub const PUBREC: u8 = 0x50;
+    pub const PUBREL: u8 = 0x62;
+    pub const PUBCOMP: u8 = 0x70;
+    pub const PINGREQ: u8 = 0xC0;
+    pub const PINGRESP: u8 = 0xD0;
+}
+
+/// Publishes feed events and statistics to an MQTT broker.
+///
+/// The connection is kept open between updates so the broker's last will message
+/// reflects whether or not the program is still running.
+pub struct MqttNotifier {
+    conn: Option<Connection>,
+    queued: Vec<(String, String)>,
+}
+
+impl MqttNotifier {
+    pub fn new() -> MqttNotifier {
+        MqttNotifier {
+            conn: None,
+            queued: Vec::new(),
+        }
+    }
+
+    /// Queues the current listeners and statistics of a feed to be published
+    /// on the next call to `publish`.
+    pub fn queue_stats(&mut self, config: &MqttConfig, feed: &Feed, stats: &ListenerStats) {
+        let base = format!("{}/feed/{}", config.topic_prefix, feed.id);
+
+        let payload = json!({
+            "listeners": feed.listeners,
+            "average": stats.average.current,
+            "unskewed_average": stats.unskewed_average,
+            "jump": stats.get_jump(feed.listeners),
+            "has_spiked": stats.has_spiked,
+            "spike_count": stats.spike_count,
+        });
+
+        self.queued
+            .push((format!("{}/listeners", base), feed.listeners.to_string()));
+        self.queued
+            .push((format!("{}/stats", base), payload.to_string()));
+    }
+
+    /// Publishes the specified events, along with any queued statistics. Statistics are
+    /// only current for a single update, so they're discarded if publishing fails.
+    pub fn publish(
+        &mut self,
+        base_config: &Config,
+        config: &MqttConfig,
+        events: &[(String, String)],
+    ) -> Result<(), MqttError> {
+        // Give the broker plenty of leeway before it considers us disconnected
+        let keep_alive = (base_config.misc.update_time * 60.0 * 2.0).min(f32::from(u16::MAX));
+
+        let messages = self
+            .queued
+            .drain(..)
+            .chain(events.iter().cloned())
+            .collect::<Vec<_>>();
+
+        let result = self.publish_all(config, keep_alive as u16, &messages);
+
+        // The connection is in an unknown state after an error, so start over next time
+        if result.is_err() {
+            self.conn = None;
+        }
+
+        result
+    }
+
+    /// Discards the queued statistics and only pings the broker, so the last will message isn't sent
+    /// while the schedule doesn't allow anything to be published.
+    pub fn keep_alive(
+        &mut self,
+        base_config: &Config,
+        config: &MqttConfig,
+    ) -> Result<(), MqttError> {
+        self.queued.clear();
+        self.publish(base_config, config, &[])
+    }
+
+    fn publish_all(
+        &mut self,
+        config: &MqttConfig,
+        keep_alive: u16,
+        messages: &[(String, String)],
+    ) -> Result<(), MqttError> {
+        // An existing connection may have been dropped by the broker since the last update
+        let alive = match self.conn {
+            Some(ref mut conn) => conn.ping().is_ok(),
+            None => false,
+        };
+
+        if !alive {
+            self.conn = Some(Connection::open(config, keep_alive)?);
+        }
+
+        if let Some(ref mut conn) = self.conn {
+            for (topic, payload) in messages {
+                conn.publish(topic, payload.as_bytes(), config.qos, config.retain)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Creates the topic and payload of an event for every spiking or alerting feed.
+pub fn events(
+    base_config: &Config,
+    config: &MqttConfig,
+    feeds: &[(Feed, ListenerStats)],
+) -> Vec<(String, String)> {
+    let topic = format!("{}/events", config.topic_prefix);
+
+    feeds
+        .iter()
+        .map(|(feed, stats)| (topic.clone(), event(base_config, feed, stats)))
+        .collect()
+}
+
+fn event(config: &Config, feed: &Feed, stats: &ListenerStats) -> String {
+    let event = Event::of(feed, stats);
+    let vars = Vars::for_feed(feed, stats, config);
+    let (title, body) = template::render(config, Backend::Mqtt, Kind::Feed(event), &vars);
+
+    let payload = json!({
+        "type": event.name(),
+        "severity": config.severity.get(feed, stats).name(),
+        "title": title,
+        "body": body,
+        "id": feed.id,
+        "name": feed.name,
+        "listeners": feed.listeners,
+        "jump": stats.get_jump(feed.listeners) as i32,
+        "state": feed.state.abbrev,
+        "county": feed.county,
+        "alert": feed.alert,
+        "spike_count": stats.spike_count,
+        "url": format!("http://broadcastify.com/listen/feed/{}", feed.id),
+    });
+
+    payload.to_string()
+}
+
+/// Creates the topic and payload of an event for an update error or recovery.
+pub fn status_event(
+    base_config: &Config,
+    config: &MqttConfig,
+    status: &Status,
+) -> (String, String) {
+    let (title, body) = status.render(base_config, Backend::Mqtt);
+
+    let payload = json!({
+        "type": status.kind().name(),
+        "severity": status.severity().name(),
+        "title": title,
+        "body": body,
+        "error": status.error,
+        "failures": status.failures,
+    });
+
+    (
+        format!("{}/events", config.topic_prefix),
+        payload.to_string(),
+    )
+}
+
+fn status_topic(config: &MqttConfig) -> String {
+    format!("{}/status", config.topic_prefix)
+}
+
+/// Appends a string prefixed by its length, which is how MQTT encodes them.
+fn push_str(buf: &mut Vec<u8>, value: &str) {
+    buf.push((value.len() >> 8) as u8);
+    buf.push(value.len() as u8);
+    buf.extend_from_slice(value.as_bytes());
+}
+
+struct Connection {
+    stream: TcpStream,
+    next_id: u16,
+}
+
+impl Connection {
+    fn open(config: &MqttConfig, keep_alive: u16) -> Result<Connection, MqttError> {
+        let stream =
+            TcpStream::connect((config.host.as_str(), config.port)).map_err(MqttError::Io)?;
+
+        stream
+            .set_read_timeout(Some(Duration::from_secs(30)))
+            .map_err(MqttError::Io)?;
+
+        let mut conn = Connection { stream, next_id: 1 };
+
+        // Clean session, with a retained will message at QoS 1
+        let mut flags = 0x02 | 0x04 | 0x08 | 0x20;
+
+        // MQTT 3.1.1 doesn't allow a password without a username
+        let password = config.username.as_ref().and(config.password.as_ref());
+
+        if config.username.is_some() {
+            flags |= 0x80;
+        }
+
+        if password.is_some() {
+            flags |= 0x40;
+        }
+
+        let mut body = Vec::new();
+        push_str(&mut body, "MQTT");
+        body.push(4); // Protocol level for MQTT 3.1.1
+        body.push(flags);
+        body.push((keep_alive >> 8) as u8);
+        body.push(keep_alive as u8);
+
+        push_str(&mut body, &config.client_id);
+        push_str(&mut body, &status_topic(config));
+        push_str(&mut body, "offline");
+
+        if let Some(ref username) = config.username {
+            push_str(&mut body, username);
+        }
+
+        if let Some(password) = password {
+            push_str(&mut body, password);
+        }
+
+        conn.write_packet(packet::CONNECT, &body)?;
+
+        let (kind, body) = conn.read_packet()?;
+
+        if kind != packet::CONNACK || body.len() < 2 {
+            return Err(MqttError::UnexpectedPacket(kind));
+        }
+
+        if body[1] != 0 {
+            return Err(MqttError::ConnectionRefused(body[1]));
+        }
+
+        conn.publish(&status_topic(config), b"online", 1, true)?;
+        Ok(conn)
+    }
+
+    fn publish(
+        &mut self,
+        topic: &str,
+        payload: &[u8],
+        qos: u8,
+        retain: bool,
+    ) -> Result<(), MqttError> {
+        let id = self.next_id;
+        self.next_id = self.next_id.checked_add(1).unwrap_or(1);
+
+        let mut body = Vec::new();
+        push_str(&mut body, topic);
+
+        if qos > 0 {
+            body.push((id >> 8) as u8);
+            body.push(id as u8);
+        }
+
+        body.extend_from_slice(payload);
+
+        let kind = packet::PUBLISH | (qos.min(2) << 1) | retain as u8;
+        self.write_packet(kind, &body)?;
+
+        match qos {
+            0 => Ok(()),
+            1 => self.expect_ack(packet::PUBACK, id),
+            _ => {
+                self.expect_ack(packet::PUBREC, id)?;
+                self.write_packet(packet::PUBREL, &[(id >> 8) as u8, id as u8])?;
+                self.expect_ack(packet::PUBCOMP, id)
+            }
+        }
+    }
+
+    fn ping(&mut self) -> Result<(), MqttError> {
+        self.write_packet(packet::PINGREQ, &[])?;
+
+        match self.read_packet()? {
+            (packet::PINGRESP, _) => Ok(()),
+            (kind, _) => Err(MqttError::UnexpectedPacket(kind)),
+        }
+    }
+
+    fn expect_ack(&mut self, expected: u8, id: u16) -> Result<(), MqttError> {
+        let (kind, body) = self.read_packet()?;
+
+        // The low bits of the packet type are reserved flags
+        if kind & 0xF0 != expected & 0xF0 || body.len() < 2 {
+            return Err(MqttError::UnexpectedPacket(kind));
+        }
+
+        if (u16::from(body[0]) << 8 | u16::from(body[1])) != id {
+            return Err(MqttError::UnexpectedPacket(kind));
+        }
+
+        Ok(())
+    }
+
+    fn write_packet(&mut self, kind: u8, body: &[u8]) -> Result<(), MqttError> {
+        let mut packet = vec![kind];
+        let mut len = body.len();
+
+        // The remaining length is encoded 7 bits at a time, with the high bit
+        // set when more bytes follow
+        loop {
+            let mut byte = (len % 128) as u8;
+            len /= 128;
+
+            if len > 0 {
+                byte |= 0x80;
+            }
+
+            packet.push(byte);
+
+            if len == 0 {
+                break;
+            }
+        }
+
+        packet.extend_from_slice(body);
+        self.stream.write_all(&packet).map_err(MqttError::Io)
+    }
+
+    fn read_packet(&mut self) -> Result<(u8, Vec<u8>), MqttError> {
+        let mut byte = [0; 1];
+
+        self.stream.read_exact(&mut byte).map_err(MqttError::Io)?;
+        let kind = byte[0];
+
+        let mut len = 0;
+
+        // The remaining length is at most 4 bytes long
+        for i in 0..4 {
+            self.stream.read_exact(&mut byte).map_err(MqttError::Io)?;
+            len |= ((byte[0] & 0x7F) as usize) << (7 * i);
+
+            if byte[0] & 0x80 == 0 {
+                let mut body = vec![0; len];
+                self.stream.read_exact(&mut body).map_err(MqttError::Io)?;
+
+                return Ok((kind, body));
+            }
+        }
+
+        Err(MqttError::InvalidLength)
+    }
+}
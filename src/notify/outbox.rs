@@ -0,0 +1,227 @@
+use super::email::Entry as EmailEntry;
+use super::exec::Invocation;
+use super::DesktopMessage;
+use chrono::{DateTime, Duration, Utc};
+use config::{Backend, Config};
+use serde_json;
+use std::fs::File;
+use std::io::{self, Read, Write};
+use std::path::PathBuf;
+
+#[derive(Fail, Debug)]
+pub enum OutboxError {
+    #[fail(display = "{}", _0)]
+    Io(#[cause] io::Error),
+
+    #[fail(display = "JSON error")]
+    Json(#[cause] serde_json::Error),
+}
+
+/// A notification that failed to be delivered.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "type", content = "data", rename_all = "lowercase")]
+pub enum Delivery {
+    Desktop(DesktopMessage),
+    Email(Vec<EmailEntry>),
+    Exec(Invocation),
+    Mqtt(String, String),
+}
+
+impl Delivery {
+    pub fn backend(&self) -> Backend {
+        match *self {
+            Delivery::Desktop(_) => Backend::Desktop,
+            Delivery::Email(_) => Backend::Email,
+            Delivery::Exec(_) => Backend::Exec,
+            Delivery::Mqtt(_, _) => Backend::Mqtt,
+        }
+    }
+
+    /// Returns true if the delivery is only useful shortly after it was created, like desktop
+    /// notifications and commands that react to a feed spiking.
+    fn expires(&self) -> bool {
+        match *self {
+            Delivery::Desktop(_) | Delivery::Exec(_) => true,
+            Delivery::Email(_) | Delivery::Mqtt(_, _) => false,
+        }
+    }
+}
+
+/// A delivery that is waiting to be retried.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Entry {
+    pub delivery: Delivery,
+    /// The number of times delivery has failed.
+    pub attempts: u32,
+    pub next_attempt: DateTime<Utc>,
+    /// The time that delivery first failed.
+    pub created: DateTime<Utc>,
+}
+
+impl Entry {
+    /// Returns true if the delivery will be too old to be useful at the specified time.
+    fn is_stale(&self, time: DateTime<Utc>, config: &Config) -> bool {
+        let max_age = Duration::seconds((config.retries.max_age_minutes * 60.0) as i64);
+        self.delivery.expires() && time.signed_duration_since(self.created) > max_age
+    }
+}
+
+/// An interface to save and load notifications that failed to be delivered.
+pub struct Outbox {
+    /// The path to the file to save and load entries from.
+    pub path: PathBuf,
+    pub entries: Vec<Entry>,
+}
+
+impl Outbox {
+    pub fn new(path: PathBuf) -> Outbox {
+        Outbox {
+            path,
+            entries: Vec::new(),
+        }
+    }
+
+    pub fn load(&mut self) -> Result<(), OutboxError> {
+        let mut contents = String::new();
+
+        File::open(&self.path)
+            .and_then(|mut file| file.read_to_string(&mut contents))
+            .map_err(OutboxError::Io)?;
+
+        let entries: Vec<Entry> = serde_json::from_str(&contents).map_err(OutboxError::Json)?;
+        self.entries.extend(entries);
+        Ok(())
+    }
+
+    pub fn save(&self) -> Result<(), OutboxError> {
+        let json = serde_json::to_string_pretty(&self.entries).map_err(OutboxError::Json)?;
+
+        File::create(&self.path)
+            .and_then(|mut file| file.write_all(json.as_bytes()))
+            .map_err(OutboxError::Io)
+    }
+
+    /// Removes and returns every entry that is ready to be retried. Entries that are too old to be
+    /// useful anymore are dropped.
+    pub fn take_due(&mut self, config: &Config) -> Vec<Entry> {
+        let now = Utc::now();
+        let count = self.entries.len();
+
+        self.entries.retain(|entry| !entry.is_stale(now, config));
+
+        let dropped = count - self.entries.len();
+
+        if dropped > 0 {
+            eprintln!(
+                "dropping {} notifications that are too old to be retried",
+                dropped
+            );
+        }
+
+        let (due, waiting) = self
+            .entries
+            .drain(..)
+            .partition(|entry| entry.next_attempt <= now);
+
+        self.entries = waiting;
+        due
+    }
+
+    /// Queues a delivery that has failed `attempts` times to be retried later. `created` is the time it first failed.
+    /// Returns false if the delivery has failed too many times, or would be too old by the next attempt, and was dropped.
+    pub fn retry_later(
+        &mut self,
+        delivery: Delivery,
+        attempts: u32,
+        created: DateTime<Utc>,
+        config: &Config,
+    ) -> bool {
+        let retries = &config.retries;
+
+        if attempts >= retries.max_attempts {
+            return false;
+        }
+
+        // The delay doubles after every failure
+        let minutes =
+            (retries.initial_minutes * 2f32.powi(attempts as i32 - 1)).min(retries.max_minutes);
+
+        let entry = Entry {
+            delivery,
+            attempts,
+            next_attempt: Utc::now() + Duration::seconds((minutes * 60.0) as i64),
+            created,
+        };
+
+        if entry.is_stale(entry.next_attempt, config) {
+            return false;
+        }
+
+        self.entries.push(entry);
+        true
+    }
+
+    /// Queues an entry that wasn't attempted to be retried again without counting it as a failure.
+    pub fn put_back(&mut self, entry: Entry) {
+        self.entries.push(entry);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Local;
+    use config::Severity;
+    use std::env;
+    use std::fs;
+    use std::process;
+
+    #[test]
+    fn entries_are_saved_and_loaded() {
+        let path = env::temp_dir().join(format!("bcnotif-outbox-{}.json", process::id()));
+        let mut outbox = Outbox::new(path.clone());
+        let now = Utc::now();
+
+        let deliveries = vec![
+            Delivery::Desktop(DesktopMessage {
+                title: "Title".into(),
+                body: "Body".into(),
+                severity: Severity::Major,
+            }),
+            Delivery::Email(vec![EmailEntry {
+                time: Local::now(),
+                title: "Title".into(),
+                body: "Body".into(),
+                severity: Severity::Critical,
+            }]),
+            Delivery::Exec(Invocation {
+                command: "true".into(),
+                args: vec!["--quiet".into()],
+                env: vec![("BCNOTIF_EVENT".into(), "spike".into())],
+                input: "{}".into(),
+                timeout: 10.0,
+            }),
+            Delivery::Mqtt("bcnotif/events".into(), "{}".into()),
+        ];
+
+        for delivery in deliveries {
+            outbox.put_back(Entry {
+                delivery,
+                attempts: 2,
+                next_attempt: now,
+                created: now,
+            });
+        }
+
+        outbox.save().unwrap();
+
+        let mut loaded = Outbox::new(path.clone());
+        loaded.load().unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(
+            format!("{:?}", loaded.entries),
+            format!("{:?}", outbox.entries)
+        );
+    }
+}
@@ -0,0 +1,198 @@
+use super::Event;
+use chrono::{Timelike, Utc};
+use config::{Backend, Config, Template, Templates};
+use feed::Feed;
+use statistics::ListenerStats;
+
+impl Backend {
+    fn templates(self, config: &Config) -> &Templates {
+        let templates = &config.templates;
+
+        match self {
+            Backend::Desktop => &templates.desktop,
+            Backend::Email => &templates.email,
+            Backend::Exec => &templates.exec,
+            Backend::Mqtt => &templates.mqtt,
+        }
+    }
+}
+
+/// The values that can be substituted into a template.
+pub struct Vars(Vec<(&'static str, String)>);
+
+impl Vars {
+    pub fn new() -> Vars {
+        Vars(Vec::new())
+    }
+
+    /// Creates variables for every field of the specified feed and its statistics.
+    pub fn for_feed(feed: &Feed, stats: &ListenerStats, config: &Config) -> Vars {
+        let duration = stats.get_incident_duration();
+        let hour = Utc::now().hour() as usize;
+
+        Vars::new()
+            .set("event", Event::of(feed, stats).name())
+            .set("severity", config.severity.get(feed, stats).name())
+            .set("id", feed.id)
+            .set("name", &feed.name)
+            .set("listeners", feed.listeners)
+            .set("state", &feed.state.abbrev)
+            .set("state_id", feed.state.id)
+            .set("county", &feed.county)
+            .set("alert", feed.alert.as_ref().map_or("", |a| a.as_str()))
+            .set(
+                "alert_line",
+                feed.alert
+                    .as_ref()
+                    .map(|a| format!("\nAlert: {}", a))
+                    .unwrap_or_default(),
+            )
+            .set(
+                "url",
+                format!("http://broadcastify.com/listen/feed/{}", feed.id),
+            )
+            .set("jump", stats.get_jump(feed.listeners) as i32)
+            .set("jump_percent", stats.get_jump_pcnt(feed.listeners).round())
+            .set("baseline", stats.get_unskewed_avg().round())
+            .set("average", stats.average.current.round())
+            .set("hourly_average", stats.average_hourly[hour].round())
+            .set("hourly_norm", stats.hourly_norm.round())
+            .set("spike_count", stats.spike_count)
+            .set(
+                "duration",
+                format!("{}h {}m", duration.num_hours(), duration.num_minutes() % 60),
+            )
+    }
+
+    pub fn set<T: ToString>(mut self, name: &'static str, value: T) -> Vars {
+        self.0.push((name, value.to_string()));
+        self
+    }
+
+    /// Replaces every `{name}` placeholder in the template with its value.
+    /// Unknown placeholders are left as-is.
+    pub fn render(&self, template: &str) -> String {
+        let mut result = String::with_capacity(template.len());
+        let mut rest = template;
+
+        while let Some(start) = rest.find('{') {
+            result.push_str(&rest[..start]);
+            rest = &rest[start..];
+
+            let value = rest.find('}').and_then(|end| {
+                let name = &rest[1..end];
+
+                self.0
+                    .iter()
+                    .find(|&&(var, _)| var == name)
+                    .map(|(_, value)| (value, end))
+            });
+
+            match value {
+                Some((value, end)) => {
+                    result.push_str(value);
+                    rest = &rest[end + 1..];
+                }
+                None => {
+                    result.push('{');
+                    rest = &rest[1..];
+                }
+            }
+        }
+
+        result.push_str(rest);
+        result
+    }
+}
+
+/// The kind of notification to find a template for.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Kind {
+    Feed(Event),
+    Error,
+    Recovered,
+}
+
+impl Kind {
+    pub fn name(self) -> &'static str {
+        match self {
+            Kind::Feed(event) => event.name(),
+            Kind::Error => "error",
+            Kind::Recovered => "recovered",
+        }
+    }
+}
+
+fn default_template(backend: Backend, kind: Kind) -> (&'static str, &'static str) {
+    match (backend, kind) {
+        (_, Kind::Error) => ("Broadcastify Update Error", "{error}"),
+        (_, Kind::Recovered) => (
+            "Broadcastify Updates Recovered",
+            "Updates are working again after {failures} failed attempts",
+        ),
+        (Backend::Desktop, Kind::Feed(Event::IncidentEnded)) => (
+            "{state} - Broadcastify Incident Ended ({index} of {total})",
+            "Name: {name}\nListeners: {listeners}\nDuration: {duration}\nLink: {url}",
+        ),
+        (Backend::Desktop, Kind::Feed(_)) => (
+            "{state} - Broadcastify Update ({index} of {total})",
+            "Name: {name}\nListeners: {listeners} (^{jump}){alert_line}\nLink: {url}",
+        ),
+        (_, Kind::Feed(Event::IncidentEnded)) => (
+            "{state} - {name} ({county}) has calmed down",
+            "Listeners: {listeners}\nDuration: {duration}\nLink: {url}",
+        ),
+        (_, Kind::Feed(_)) => (
+            "{state} - {name} ({county})",
+            "Listeners: {listeners} (^{jump}){alert_line}\nLink: {url}",
+        ),
+    }
+}
+
+fn configured(templates: &Templates, kind: Kind) -> &Template {
+    match kind {
+        Kind::Feed(Event::Spike) => &templates.spike,
+        Kind::Feed(Event::Alert) => &templates.alert,
+        Kind::Feed(Event::IncidentEnded) => &templates.incident_ended,
+        Kind::Error => &templates.error,
+        Kind::Recovered => &templates.recovered,
+    }
+}
+
+/// Renders the title and body for the specified backend and kind of notification,
+/// using the configured templates if they exist.
+pub fn render(config: &Config, backend: Backend, kind: Kind, vars: &Vars) -> (String, String) {
+    let template = configured(backend.templates(config), kind);
+    let (title, body) = default_template(backend, kind);
+
+    let title = template.title.as_ref().map_or(title, |t| t.as_str());
+    let body = template.body.as_ref().map_or(body, |b| b.as_str());
+
+    (vars.render(title), vars.render(body))
+}
+
+/// Renders a single line used to describe a feed in a summary notification.
+pub fn render_summary_line(config: &Config, backend: Backend, vars: &Vars) -> String {
+    let line = backend
+        .templates(config)
+        .summary_line
+        .as_ref()
+        .map_or("{state} - {name}: {listeners} (^{jump}){alert_line}", |l| {
+            l.as_str()
+        });
+
+    vars.render(line)
+}
+
+/// Renders the title of a summary notification.
+pub fn render_summary_title(config: &Config, backend: Backend, vars: &Vars) -> String {
+    let title = backend
+        .templates(config)
+        .summary_title
+        .as_ref()
+        .map_or("{group} - Broadcastify Update ({total} feeds)", |t| {
+            t.as_str()
+        });
+
+    vars.render(title)
+}
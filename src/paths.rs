@@ -0,0 +1,168 @@
+use config;
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// The files that are saved between runs, which are stored separately from the config file.
+const DATA_FILES: [&str; 3] = ["averages.csv", "mutes.csv", "outbox.json"];
+
+/// Keeps track of where the config file and saved data are stored.
+pub struct Paths {
+    /// The config file that was specified on the command line or through the environment.
+    config_file: Option<PathBuf>,
+    pub config_dir: PathBuf,
+    pub data_dir: PathBuf,
+    /// The directory of the executable, which everything used to be stored in.
+    legacy_dir: PathBuf,
+}
+
+impl Paths {
+    /// Finds the directories to use. The specified paths come first, followed by the BCNOTIF_CONFIG and
+    /// BCNOTIF_DATA_DIR environment variables, and then the standard directories for the platform.
+    /// The directory of the executable is used when the standard directories can't be found.
+    pub fn new(config_file: Option<PathBuf>, data_dir: Option<PathBuf>) -> io::Result<Paths> {
+        let mut legacy_dir = env::current_exe()?;
+        legacy_dir.pop();
+
+        let (config_dir, default_data_dir) = match base_dirs() {
+            Some((config_base, data_base)) => {
+                (config_base.join("bcnotif"), data_base.join("bcnotif"))
+            }
+            None => (legacy_dir.clone(), legacy_dir.clone()),
+        };
+
+        Ok(Paths {
+            config_file: config_file.or_else(|| env_path("BCNOTIF_CONFIG")),
+            config_dir,
+            data_dir: data_dir
+                .or_else(|| env_path("BCNOTIF_DATA_DIR"))
+                .unwrap_or(default_data_dir),
+            legacy_dir,
+        })
+    }
+
+    /// Gets the path of the config file. A config file in the directory of the executable is only used
+    /// when there isn't one in the config directory.
+    pub fn config_file(&self) -> PathBuf {
+        if let Some(ref path) = self.config_file {
+            return path.clone();
+        }
+
+        let path = config::find_file(&self.config_dir);
+
+        if path.exists() {
+            return path;
+        }
+
+        let legacy = config::find_file(&self.legacy_dir);
+
+        if legacy.exists() {
+            legacy
+        } else {
+            path
+        }
+    }
+
+    /// Gets the path of a file that is saved between runs. Like the config file, it's only read from
+    /// the directory of the executable when it doesn't exist in the data directory.
+    pub fn data_file(&self, name: &str) -> PathBuf {
+        let path = self.data_dir.join(name);
+        let legacy = self.legacy_dir.join(name);
+
+        if !path.exists() && legacy.exists() {
+            legacy
+        } else {
+            path
+        }
+    }
+
+    /// Creates the data and config directories and moves files from the directory of the executable to where
+    /// they're stored now. Files that can't be moved keep being used from their old location.
+    pub fn prepare(&self) -> io::Result<()> {
+        fs::create_dir_all(&self.data_dir)?;
+
+        if self.config_file.is_none() {
+            // The config file's directory is watched for changes, so it has to exist even before the file does
+            fs::create_dir_all(&self.config_dir)?;
+
+            let legacy = config::find_file(&self.legacy_dir);
+
+            if legacy.exists() && !config::find_file(&self.config_dir).exists() {
+                if let Some(name) = legacy.file_name() {
+                    migrate(&legacy, &self.config_dir.join(name));
+                }
+            }
+        }
+
+        for name in &DATA_FILES {
+            let legacy = self.legacy_dir.join(name);
+            let path = self.data_dir.join(name);
+
+            if legacy.exists() && !path.exists() {
+                migrate(&legacy, &path);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Gets the base directories for config files and data on Windows.
+#[cfg(windows)]
+fn base_dirs() -> Option<(PathBuf, PathBuf)> {
+    let config = env_path("APPDATA")?;
+    let data = env_path("LOCALAPPDATA").unwrap_or_else(|| config.clone());
+    Some((config, data))
+}
+
+/// Gets the base directories for config files and data from the XDG base directory specification.
+#[cfg(not(windows))]
+fn base_dirs() -> Option<(PathBuf, PathBuf)> {
+    // The specification says relative paths should be ignored
+    let xdg_path = |name| env_path(name).filter(|path| path.is_absolute());
+    let home = env_path("HOME");
+
+    let config =
+        xdg_path("XDG_CONFIG_HOME").or_else(|| home.as_ref().map(|home| home.join(".config")))?;
+    let data = xdg_path("XDG_DATA_HOME").or_else(|| home.map(|home| home.join(".local/share")))?;
+
+    Some((config, data))
+}
+
+/// Gets a path from an environment variable, if it's set to something.
+fn env_path(name: &str) -> Option<PathBuf> {
+    env::var_os(name)
+        .filter(|value| !value.is_empty())
+        .map(PathBuf::from)
+}
+
+/// Moves a file and prints where it was moved to, or why it couldn't be.
+fn migrate(from: &Path, to: &Path) {
+    match move_file(from, to) {
+        Ok(_) => println!("moved {} to {}", from.display(), to.display()),
+        Err(err) => eprintln!(
+            "failed to move {} to {}: {}",
+            from.display(),
+            to.display(),
+            err
+        ),
+    }
+}
+
+fn move_file(from: &Path, to: &Path) -> io::Result<()> {
+    if let Some(dir) = to.parent() {
+        fs::create_dir_all(dir)?;
+    }
+
+    // Renaming doesn't work across filesystems
+    if fs::rename(from, to).is_ok() {
+        return Ok(());
+    }
+
+    fs::copy(from, to)?;
+
+    // The old directory can be read-only, in which case the copy is used from now on
+    let _ = fs::remove_file(from);
+    Ok(())
+}
@@ -1,5 +1,5 @@
+use chrono::{DateTime, Timelike, Utc};
 use config::Config;
-use chrono::{Timelike, Utc};
 use csv;
 use feed::Feed;
 use std::collections::HashMap;
@@ -166,6 +166,12 @@ pub struct ListenerStats {
     pub has_spiked: bool,
     /// Represents the number of times the feed has spiked consecutively.
     pub spike_count: u32,
+    /// The time the feed's current (or just ended) incident started spiking.
+    pub spike_start: Option<DateTime<Utc>>,
+    /// Indicates whether or not the feed stopped spiking during the last update.
+    pub incident_ended: bool,
+    /// The average number of listeners for the current hour before the last update.
+    pub hourly_norm: f32,
 }
 
 impl ListenerStats {
@@ -184,6 +190,9 @@ impl ListenerStats {
             average_hourly: hourly,
             has_spiked: false,
             spike_count: 0,
+            spike_start: None,
+            incident_ended: false,
+            hourly_norm: 0.0,
         }
     }
 
@@ -195,6 +204,9 @@ impl ListenerStats {
             average_hourly: hourly,
             has_spiked: false,
             spike_count: 0,
+            spike_start: None,
+            incident_ended: false,
+            hourly_norm: 0.0,
         }
     }
 
@@ -202,15 +214,31 @@ impl ListenerStats {
     pub fn update(&mut self, hour: usize, feed: &Feed, config: &Config) {
         self.has_spiked = self.is_spiking(feed, config);
 
+        self.incident_ended = !self.has_spiked && self.spike_count > 0;
+
         self.spike_count = if self.has_spiked {
             self.spike_count + 1
         } else {
             0
         };
 
+        // The start time is kept for one extra update after an incident ends
+        // so its duration can still be reported
+        if self.has_spiked {
+            self.spike_start = self.spike_start.or_else(|| Some(Utc::now()));
+        } else if !self.incident_ended {
+            self.spike_start = None;
+        }
+
+        self.hourly_norm = self.average_hourly[hour];
+
         self.average.add_sample(feed.listeners as i32);
         self.update_unskewed_average(feed.listeners as f32, config);
-        self.average_hourly[hour] = self.get_unskewed_avg();
+
+        // Unusual days like holidays would throw off the averages for the rest of the year
+        if !config.skips_learning(feed) {
+            self.average_hourly[hour] = self.get_unskewed_avg();
+        }
     }
 
     /// Returns true if the specified feed is currently spiking in listeners
@@ -225,8 +253,8 @@ impl ListenerStats {
 
         // If a feed has a low number of listeners, use a higher threshold to
         // make the calculation less sensitive to very small listener jumps
-        let threshold = if listeners < 50.0 {
-            spike.jump + (50.0 - listeners) * spike.low_listener_increase
+        let threshold = if listeners < spike.low_listener_cutoff {
+            spike.jump + (spike.low_listener_cutoff - listeners) * spike.low_listener_increase
         } else {
             // Otherwise, use a lower threshold based off of how fast the feed's
             // listeners are rising to encourage more updates during large incidents
@@ -236,7 +264,15 @@ impl ListenerStats {
             spike.jump - rise_amount.min(spike.jump - 0.01)
         };
 
-        listeners - self.average.current >= listeners * threshold
+        let mut required = (listeners * threshold).max(spike.minimum_jump);
+
+        // A feed that is already spiking only has to keep part of the jump, so it doesn't
+        // flap on and off while its listeners hover around the threshold
+        if self.has_spiked {
+            required *= spike.exit_jump_ratio;
+        }
+
+        listeners - self.average.current >= required
     }
 
     fn update_unskewed_average(&mut self, listeners: f32, config: &Config) {
@@ -279,8 +315,70 @@ impl ListenerStats {
     pub fn get_jump(&self, listeners: u32) -> f32 {
         listeners as f32 - self.get_unskewed_avg()
     }
+
+    /// Returns the difference in listeners from the unskewed average as a percentage of it.
+    pub fn get_jump_pcnt(&self, listeners: u32) -> f32 {
+        let average = self.get_unskewed_avg();
+
+        if average > 0.0 {
+            self.get_jump(listeners) / average * 100.0
+        } else {
+            0.0
+        }
+    }
+
+    /// Returns how long the feed has been spiking for, or how long its last incident
+    /// lasted if it just ended.
+    pub fn get_incident_duration(&self) -> ::chrono::Duration {
+        match self.spike_start {
+            Some(start) => Utc::now().signed_duration_since(start),
+            None => ::chrono::Duration::zero(),
+        }
+    }
 }
 
 fn lerp(v0: f32, v1: f32, t: f32) -> f32 {
     (1. - t) * v0 + t * v1
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use config::Spike;
+    use feed::test_feed;
+
+    fn feed(listeners: u32) -> Feed<'static> {
+        Feed {
+            listeners,
+            ..test_feed(1, "Feed", "Sacramento")
+        }
+    }
+
+    #[test]
+    fn spiking_feeds_only_have_to_keep_part_of_the_jump() {
+        let config = Config {
+            global_spike: Spike {
+                jump: 0.5,
+                high_listener_dec: 0.0,
+                low_listener_cutoff: 0.0,
+                exit_jump_ratio: 0.5,
+                ..Spike::default()
+            },
+            ..Config::default()
+        };
+
+        // With an average of 100, a feed has to reach 200 listeners to start spiking,
+        // and then only has to stay above 134 to keep spiking
+        let mut stats = ListenerStats::with_data(100, [0.0; ListenerStats::HOURLY_SIZE]);
+
+        assert!(!stats.is_spiking(&feed(150), &config));
+        assert!(!stats.is_spiking(&feed(199), &config));
+        assert!(stats.is_spiking(&feed(200), &config));
+
+        stats.has_spiked = true;
+
+        assert!(stats.is_spiking(&feed(150), &config));
+        assert!(stats.is_spiking(&feed(134), &config));
+        assert!(!stats.is_spiking(&feed(133), &config));
+    }
+}
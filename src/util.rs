@@ -1,6 +1,7 @@
-use std::path::Path;
+use chrono::{DateTime, Duration, Local, NaiveDate, NaiveDateTime, TimeZone, Utc};
 use std::fs::File;
 use std::io::{self, Read};
+use std::path::Path;
 
 pub fn read_file(path: &Path) -> io::Result<String> {
     let mut file = File::open(path)?;
@@ -10,3 +11,60 @@ pub fn read_file(path: &Path) -> io::Result<String> {
 
     Ok(buffer)
 }
+
+/// Parses a time in local time like "2018-06-01 18:00" or "2018-06-01T18:00:00", a date like "2018-06-01"
+/// (which is the start of the day), or an RFC 3339 time with an offset.
+pub fn parse_time(text: &str) -> Option<DateTime<Utc>> {
+    const FORMATS: [&str; 4] = [
+        "%Y-%m-%d %H:%M",
+        "%Y-%m-%d %H:%M:%S",
+        "%Y-%m-%dT%H:%M",
+        "%Y-%m-%dT%H:%M:%S",
+    ];
+
+    let text = text.trim();
+
+    if let Ok(time) = DateTime::parse_from_rfc3339(text) {
+        return Some(time.with_timezone(&Utc));
+    }
+
+    let time = FORMATS
+        .iter()
+        .filter_map(|format| NaiveDateTime::parse_from_str(text, format).ok())
+        .next()
+        .or_else(|| {
+            NaiveDate::parse_from_str(text, "%Y-%m-%d")
+                .ok()
+                .and_then(|date| date.and_hms_opt(0, 0, 0))
+        })?;
+
+    Local
+        .from_local_datetime(&time)
+        .earliest()
+        .map(|time| time.with_timezone(&Utc))
+}
+
+/// The longest duration that can be parsed, which is far longer than anything needs to last.
+const MAX_DURATION_MINUTES: f64 = 60.0 * 24.0 * 365.0 * 100.0;
+
+/// Parses a duration like "30m", "1.5h", "2d", or "1w". Durations longer than 100 years aren't allowed.
+pub fn parse_duration(text: &str) -> Option<Duration> {
+    let text = text.trim();
+
+    let unit = match text.chars().last()? {
+        'm' => 1.0,
+        'h' => 60.0,
+        'd' => 60.0 * 24.0,
+        'w' => 60.0 * 24.0 * 7.0,
+        _ => return None,
+    };
+
+    let amount = text[..text.len() - 1].parse::<f64>().ok()?;
+    let minutes = amount * unit;
+
+    if amount <= 0.0 || !minutes.is_finite() || minutes > MAX_DURATION_MINUTES {
+        return None;
+    }
+
+    Some(Duration::seconds((minutes * 60.0) as i64))
+}
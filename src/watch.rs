@@ -0,0 +1,117 @@
+use std::io;
+use std::path::Path;
+use std::sync::mpsc::{Receiver, RecvTimeoutError};
+use std::thread;
+use std::time::Duration;
+
+/// How long the config file has to go without changing before it's reloaded, since editors often
+/// save a file in several steps.
+const SETTLE_TIME: Duration = Duration::from_millis(250);
+
+/// Watches the config file so changes to it can be applied as soon as they're saved.
+pub struct ConfigWatcher {
+    /// Receives a message every time the config file changes. It's None when the file can't be watched,
+    /// in which case changes are only applied on the next update.
+    changes: Option<Receiver<()>>,
+}
+
+impl ConfigWatcher {
+    pub fn new(path: &Path) -> ConfigWatcher {
+        let changes = match watch(path) {
+            Ok(changes) => changes,
+            Err(err) => {
+                eprintln!(
+                    "failed to watch {} for changes, so they will be applied on the next update: {}",
+                    path.display(),
+                    err
+                );
+
+                None
+            }
+        };
+
+        ConfigWatcher { changes }
+    }
+
+    /// Waits for the timeout to pass, or for the config file to change. Returns true if it changed.
+    pub fn wait(&mut self, timeout: Duration) -> bool {
+        let result = match self.changes {
+            Some(ref changes) => match changes.recv_timeout(timeout) {
+                Ok(_) => {
+                    while changes.recv_timeout(SETTLE_TIME).is_ok() {}
+                    return true;
+                }
+                Err(err) => err,
+            },
+            None => {
+                thread::sleep(timeout);
+                return false;
+            }
+        };
+
+        // The watching thread only stops when it can't read events anymore, and it already printed why
+        if let RecvTimeoutError::Disconnected = result {
+            self.changes = None;
+            thread::sleep(timeout);
+        }
+
+        false
+    }
+}
+
+/// Starts watching the config file on another thread, and returns the receiving end of its changes.
+#[cfg(target_os = "linux")]
+fn watch(path: &Path) -> io::Result<Option<Receiver<()>>> {
+    use inotify::{Inotify, WatchMask};
+    use std::sync::mpsc;
+
+    let name = match path.file_name() {
+        Some(name) => name.to_owned(),
+        None => return Err(io::Error::new(io::ErrorKind::InvalidInput, "not a file")),
+    };
+
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+
+    let mut inotify = Inotify::init()?;
+
+    // Editors usually save by replacing the file, which would remove a watch on the file itself
+    inotify.add_watch(
+        dir,
+        WatchMask::CLOSE_WRITE | WatchMask::MOVED_TO | WatchMask::MOVED_FROM | WatchMask::DELETE,
+    )?;
+
+    let (tx, rx) = mpsc::channel();
+
+    thread::spawn(move || {
+        let mut buffer = [0; 4096];
+
+        loop {
+            let events = match inotify.read_events_blocking(&mut buffer) {
+                Ok(events) => events,
+                Err(err) => {
+                    eprintln!("stopped watching the config file for changes: {}", err);
+                    return;
+                }
+            };
+
+            let changed = events
+                .into_iter()
+                .any(|event| event.name == Some(name.as_os_str()));
+
+            if changed && tx.send(()).is_err() {
+                return;
+            }
+        }
+    });
+
+    Ok(Some(rx))
+}
+
+/// Changes can only be watched for on Linux, so everywhere else they're applied on the next update.
+#[cfg(not(target_os = "linux"))]
+fn watch(_: &Path) -> io::Result<Option<Receiver<()>>> {
+    Ok(None)
+}